@@ -1,5 +1,4 @@
 use std::io;
-use std::process::exit;
 use std::time::Duration;
 
 use log::LevelFilter;
@@ -10,7 +9,7 @@ use socha::socha_com::ComHandler;
 
 fn main() -> io::Result<()> {
     log_to_file("com.log", LevelFilter::Info).unwrap();
-    let mut com = match ComHandler::join("localhost:13050", None) {
+    let mut com: ComHandler = match ComHandler::join("localhost:13050", None) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("connect/join failed: {:?}", e);
@@ -26,15 +25,18 @@ fn main() -> io::Result<()> {
                 }
                 ComMessage::Left(left) => {
                     eprintln!("left room {}", left.room_id);
-                    exit(0)
+                    if let Err(e) = com.close() {
+                        eprintln!("closing the connection failed: {:?}", e);
+                    }
+                    break;
                 }
-                ComMessage::Room(room_msg) => match *room_msg {
+                ComMessage::Room { msg, .. } => match *msg {
                     RoomMessage::Memento(state) => {
                         eprintln!("got board: \n{}", state.board);
                         eprintln!("turn {}, class {:?}", state.turn, state.class);
                     }
-                    RoomMessage::WelcomeMessage => {
-                        eprintln!("got welcome message");
+                    RoomMessage::WelcomeMessage(welcome) => {
+                        eprintln!("got welcome message, playing as team {:?}", welcome.team);
                     }
                     RoomMessage::MoveRequest => {
                         eprintln!("got move request");
@@ -44,6 +46,9 @@ fn main() -> io::Result<()> {
                     }
                 },
                 ComMessage::Admin(_) => {}
+                ComMessage::Error(err) => {
+                    eprintln!("server error: {}", err);
+                }
             },
             Err(ReceiveErr::XmlError(e)) => {
                 eprintln!("XML parse error: {:?}", e);
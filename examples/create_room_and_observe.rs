@@ -3,91 +3,84 @@ use std::time::Duration;
 
 use log::LevelFilter;
 use simple_logging::log_to_file;
-use socha::error::ReceiveErr;
+use socha::admin::{AdminClient, StepOutcome};
+use socha::error::AdminErr;
 use socha::internal::{ComMessage, RoomMessage};
-use socha::socha_com::{ComHandler, PrepareSlot};
+use socha::season::{piranhas::Piranhas, Season};
+use socha::socha_com::PrepareSlot;
 
-// todo: finish
 fn main() -> io::Result<()> {
     log_to_file("admin_com.log", LevelFilter::Info).unwrap();
 
-    let mut com = match ComHandler::connect_to_server("localhost:13050") {
-        Ok(c) => c,
+    // auth
+    let mut admin: AdminClient = match AdminClient::connect_and_authenticate(
+        "localhost:13050",
+        &Piranhas::game_type(),
+        "examplepassword",
+    ) {
+        Ok(a) => a,
         Err(e) => {
-            eprintln!("connect failed: {:?}", e);
-            return Err(std::io::Error::other("connect failed"));
+            eprintln!("connect/authenticate failed: {:?}", e);
+            return Err(std::io::Error::other("admin auth failed"));
         }
     };
+    eprintln!("authenticated");
 
-    // auth
-    if let Err(e) = com.send_admin_authenticate("examplepassword") {
-        eprintln!("admin authenticate failed: {:?}", e);
-        return Err(std::io::Error::other("admin auth failed"));
-    }
-    eprintln!("sent admin authenticate");
-
-    // todo: slots system not fully tested yet
     let slots = [
         PrepareSlot::new("Player_1".to_string(), true, true),
         PrepareSlot::new("Player_2".to_string(), true, true),
     ];
-    if let Err(e) = com.send_admin_prepare(true, &slots) {
-        eprintln!("prepare failed: {:?}", e);
-        return Err(std::io::Error::other("prepare failed"));
-    }
-    eprintln!("sent prepare (paused)");
+    let prepared = match admin.prepare(true, &slots) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("prepare failed: {:?}", e);
+            return Err(std::io::Error::other("prepare failed"));
+        }
+    };
+    eprintln!(
+        "prepared paused room '{}' with reservations {:?}",
+        prepared.room_id, prepared.reservations
+    );
 
-    let mut sent_observe = false;
+    if let Err(e) = admin.observe(&prepared.room_id) {
+        eprintln!("observe failed: {:?}", e);
+        return Err(std::io::Error::other("observe failed"));
+    }
+    eprintln!("observing, start two clients with the reservations above");
 
+    // wait for the first memento, which is sent once both players joined
     loop {
-        match com.wait_for_com_message(Duration::from_secs(50)) {
-            Ok(com_message) => {
-                if !sent_observe {
-                    if let Some(room_id) = com.room_id.clone() {
-                        eprintln!("found room_id '{}', sending observe", room_id);
-                        if let Err(e) = com.send_admin_observe(&room_id) {
-                            eprintln!("send_observe failed: {:?}", e);
-                            return Err(std::io::Error::other("observe failed"));
-                        }
-                    }
-                    sent_observe = true;
-                }
-
-                match com_message {
-                    ComMessage::Joined(joined) => {
-                        eprintln!("(incoming) joined room {}", joined.room_id);
-                    }
-                    ComMessage::Left(left) => {
-                        eprintln!("(incoming) left room {}", left.room_id);
-                    }
-                    ComMessage::Room(room_msg) => match *room_msg {
-                        RoomMessage::Memento(state) => {
-                            eprintln!("(incoming) got board: \n{}", state.board);
-                            eprintln!("turn {}, class {:?}", state.turn, state.class);
-                        }
-                        RoomMessage::WelcomeMessage => {
-                            eprintln!("(incoming) got welcome message");
-                        }
-                        RoomMessage::MoveRequest => {
-                            eprintln!("(incoming) got move request");
-                        }
-                        RoomMessage::Result(result) => {
-                            eprintln!("(incoming) got result: \n{:#?}", result);
-                        }
-                    },
-                    ComMessage::Admin(admin) => {
-                        eprintln!("admin message: {:?}", admin);
-                    }
+        match admin.next_message(Duration::from_secs(50)) {
+            Ok(ComMessage::Room { room_id, msg }) if room_id == prepared.room_id => {
+                if let RoomMessage::Memento(state) = *msg {
+                    eprintln!("(incoming) got board: \n{}", state.board);
+                    break;
                 }
             }
-
-            Err(ReceiveErr::XmlError(e)) => {
-                eprintln!("XML parse error: {:?}", e);
-                continue;
+            Ok(other) => eprintln!("(incoming) {:?}", other),
+            Err(e) => {
+                eprintln!("fatal receive error: {:#?}", e);
+                return Err(std::io::Error::other("receive failed"));
             }
+        }
+    }
 
+    // step through the game
+    loop {
+        match admin.step(&prepared.room_id) {
+            Ok(StepOutcome::Moved(state)) => {
+                eprintln!("turn {}: \n{}", state.turn, state.board);
+            }
+            Ok(StepOutcome::GameOver(result)) => {
+                eprintln!("game over: \n{:#?}", result);
+                break;
+            }
+            Err(AdminErr::Server(err)) => {
+                eprintln!("server refused to step: {}", err);
+                break;
+            }
             Err(e) => {
-                eprintln!("fatal receive error: {:#?}", e);
+                eprintln!("fatal error while stepping: {:#?}", e);
                 break;
             }
         }
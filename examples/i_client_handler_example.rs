@@ -1,9 +1,12 @@
-use log::LevelFilter;
-use simple_logging::log_to_file;
-use socha::{error::ComError, internal::GameState};
+use socha::{
+    error::{HandlerErr, RunErr},
+    internal::GameState,
+};
 
-use socha::i_client_handler::handler_trait::IClientHandler;
-use socha::i_client_handler::start_iclient;
+use socha::i_client_handler::args::run_from_args;
+use socha::i_client_handler::cancel::CancelToken;
+use socha::i_client_handler::handler_trait::{HandlerControl, IClientHandler};
+use socha::i_client_handler::move_context::MoveContext;
 
 use rand::Rng;
 
@@ -13,37 +16,33 @@ pub struct Logic {
 }
 
 impl IClientHandler for Logic {
-    fn calculate_move(&mut self) -> socha::neutral::Move {
-        println!("move-request erhalten");
+    fn calculate_move(&mut self, ctx: &MoveContext) -> Result<socha::neutral::Move, HandlerErr> {
+        println!("move-request erhalten, {:?} zeit übrig", ctx.time_left());
         let mut rng = rand::rng();
-        let moves = self.game_state.possible_moves();
+        let moves = ctx.state.possible_moves();
+        if moves.is_empty() {
+            return Err("keine legalen züge".into());
+        }
 
-        moves[rng.random_range(0..moves.len())]
+        Ok(moves[rng.random_range(0..moves.len())])
     }
 
-    fn on_gamestate_update(&mut self, state: socha::internal::GameState) {
+    fn on_gamestate_update(&mut self, state: socha::internal::GameState) -> HandlerControl {
         self.game_state = state;
+        HandlerControl::Continue
     }
 
-    fn while_waiting(&mut self, cancel_handler: socha::i_client_handler::ComCancelHandler) {
-        loop {
-            if cancel_handler.is_cancelled() {
-                break;
-            }
-        }
+    fn while_waiting(&mut self, _state: &GameState, cancel: CancelToken) {
+        let reason = cancel.wait();
+        println!("warten beendet: {:?}", reason);
     }
 }
 /// random bot Beispiel
-fn main() -> Result<(), ComError> {
-    // logs in datei speichern
-    log_to_file("com.log", LevelFilter::Info).unwrap();
+/// wird vom wettkampf-server mit --host, --port und --reservation gestartet,
+/// ohne argumente verbindet er sich mit localhost:13050. logs landen in socha_client.log
+fn main() -> Result<(), RunErr> {
     let mut handler = Logic::default();
-    start_iclient(
-        "localhost:13050",
-        None,
-        &mut handler,
-        std::time::Duration::from_millis(2),
-        std::time::Duration::from_secs_f64(1.0), // maximale wartzeit für die while_waiting funktion
-    )?;
+    let summary = run_from_args(&mut handler)?;
+    println!("spiel beendet: {:?}", summary.result.map(|res| res.winner));
     Ok(())
 }
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use socha::package::{package, verify_package, BuildTarget, PackageConfig};
+
+/// baut einen bot im release modus und packt ihn als zip für den wettkampf-server
+/// aufruf: package_bot (--bin <name> | --example <name>) [--manifest-dir <dir>]
+///         [--include <datei>]... [--out <zip>] [--target <triple>] [--no-verify]
+/// --target baut für ein anderes ziel, z.b. x86_64-unknown-linux-gnu, wenn nicht unter linux gepackt wird
+/// ohne --no-verify spielt der gepackte bot danach ein spiel gegen sich selbst auf einem mock server
+fn main() {
+    let mut target = None;
+    let mut manifest_dir = PathBuf::from(".");
+    let mut extra_files = Vec::new();
+    let mut out_path = PathBuf::from("bot.zip");
+    let mut verify = true;
+    let mut target_triple = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin" => target = args.next().map(BuildTarget::Bin),
+            "--example" => target = args.next().map(BuildTarget::Example),
+            "--manifest-dir" => {
+                manifest_dir = args.next().map(PathBuf::from).unwrap_or(manifest_dir)
+            }
+            "--include" => extra_files.extend(args.next().map(PathBuf::from)),
+            "--out" => out_path = args.next().map(PathBuf::from).unwrap_or(out_path),
+            "--target" => target_triple = args.next(),
+            "--no-verify" => verify = false,
+            other => eprintln!("ignoring unknown argument '{}'", other),
+        }
+    }
+    let Some(target) = target else {
+        eprintln!("usage: package_bot (--bin <name> | --example <name>) [--manifest-dir <dir>] [--include <file>]... [--out <zip>] [--target <triple>] [--no-verify]");
+        std::process::exit(2);
+    };
+
+    let config = PackageConfig {
+        manifest_dir,
+        target,
+        extra_files,
+        out_path,
+        target_triple,
+    };
+    let zip_path = match package(&config) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("packaging failed: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("wrote {}", zip_path.display());
+
+    if verify {
+        let work_dir = std::env::temp_dir().join(format!("socha-package-{}", std::process::id()));
+        match verify_package(&zip_path, &work_dir) {
+            Ok(report) => eprintln!(
+                "verified: the packaged bot played a full game ({:?})",
+                report.result.winner
+            ),
+            Err(e) => {
+                eprintln!("verification failed: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
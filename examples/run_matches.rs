@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use log::LevelFilter;
+use simple_logging::log_to_file;
+use socha::mock_server::{MockServer, MockServerConfig};
+use socha::orchestrator::{BotCommand, MatchConfig, Orchestrator};
+
+/// lässt zwei bots gegeneinander spielen
+/// aufruf: run_matches <bot_a> <bot_b> [anzahl_spiele] [--mock]
+/// mit --mock wird statt des echten servers ein lokaler mock server gestartet
+fn main() {
+    log_to_file("matches.log", LevelFilter::Info).unwrap();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let use_mock = args.iter().any(|arg| arg == "--mock");
+    args.retain(|arg| arg != "--mock");
+    if args.len() < 2 {
+        eprintln!("usage: run_matches <bot_a> <bot_b> [games] [--mock]");
+        return;
+    }
+    let games = args.get(2).and_then(|g| g.parse().ok()).unwrap_or(2);
+    let bot_a = BotCommand::new("bot_a", PathBuf::from(&args[0]));
+    let bot_b = BotCommand::new("bot_b", PathBuf::from(&args[1]));
+
+    let mock = if use_mock {
+        Some(MockServer::spawn("127.0.0.1:0", MockServerConfig::default()).unwrap())
+    } else {
+        None
+    };
+    let mut config = MatchConfig::default();
+    if let Some(mock) = &mock {
+        config.addr = mock.addr().to_string();
+    }
+    config.log_dir = Some(PathBuf::from("."));
+
+    let mut orchestrator: Orchestrator = match Orchestrator::connect(config) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("connecting failed: {:?}", e);
+            return;
+        }
+    };
+    let report = orchestrator.run_batch(&bot_a, &bot_b, games);
+    for (i, res) in report.matches.iter().enumerate() {
+        match res {
+            Ok(m) => eprintln!(
+                "game {}: {} vs {}, winner: {:?}",
+                i + 1,
+                m.players.0,
+                m.players.1,
+                m.winner_name()
+            ),
+            Err(e) => eprintln!("game {} failed: {:?}", i + 1, e),
+        }
+    }
+    eprintln!(
+        "bot_a: {} wins, bot_b: {} wins, {} draws, {} failed",
+        report.wins("bot_a"),
+        report.wins("bot_b"),
+        report.draws(),
+        report.failed()
+    );
+}
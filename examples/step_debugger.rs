@@ -0,0 +1,121 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::LevelFilter;
+use simple_logging::log_to_file;
+use socha::debugger::{DebugStep, StepDebugger};
+use socha::mock_server::{MockServer, MockServerConfig};
+use socha::orchestrator::BotCommand;
+use socha::season::{piranhas::Piranhas, Season};
+
+/// spielt eine partie zweier bots zug für zug durch
+/// aufruf: step_debugger <bot_one> <bot_two> [--mock]
+/// befehle: enter oder s = ein zug, s <n> = n züge, c = bis zum ende weiterspielen, q = abbrechen
+fn main() {
+    log_to_file("debugger.log", LevelFilter::Info).unwrap();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let use_mock = args.iter().any(|arg| arg == "--mock");
+    args.retain(|arg| arg != "--mock");
+    if args.len() < 2 {
+        eprintln!("usage: step_debugger <bot_one> <bot_two> [--mock]");
+        return;
+    }
+    let bot_one = BotCommand::new("bot_one", PathBuf::from(&args[0]));
+    let bot_two = BotCommand::new("bot_two", PathBuf::from(&args[1]));
+
+    let mock = if use_mock {
+        Some(MockServer::spawn("127.0.0.1:0", MockServerConfig::default()).unwrap())
+    } else {
+        None
+    };
+    let addr = mock
+        .as_ref()
+        .map_or("localhost:13050".to_string(), |m| m.addr().to_string());
+    let (host, port) = addr.rsplit_once(':').unwrap();
+
+    let mut debugger = match StepDebugger::prepare(
+        &addr,
+        &Piranhas::game_type(),
+        &MockServerConfig::default().password,
+        (&bot_one.name, &bot_two.name),
+    ) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("preparing the room failed: {:?}", e);
+            return;
+        }
+    };
+    let (res_one, res_two) = debugger.reservations().clone();
+    let log_dir = PathBuf::from(".");
+    let mut children = Vec::new();
+    for (bot, reservation) in [(&bot_one, res_one), (&bot_two, res_two)] {
+        match bot.spawn(host, port, &reservation, Some(&log_dir), &bot.name) {
+            Ok(child) => children.push(child),
+            Err(e) => eprintln!("starting {} failed: {:?}", bot.name, e),
+        }
+    }
+
+    match debugger.wait_for_start(Duration::from_secs(30)) {
+        Ok(view) => println!("{}", view),
+        Err(e) => {
+            eprintln!("the game did not start: {:?}", e);
+            let _ = debugger.cancel();
+            return;
+        }
+    }
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let mut words = line.split_whitespace();
+        match words.next().unwrap_or("s") {
+            "s" => {
+                let steps: u32 = words.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+                let mut over = false;
+                for _ in 0..steps {
+                    match debugger.step() {
+                        Ok(DebugStep::Moved(view)) => println!("{}", view),
+                        Ok(DebugStep::GameOver(result)) => {
+                            println!("game over: \n{:#?}", result);
+                            over = true;
+                            break;
+                        }
+                        Err(e) => {
+                            eprintln!("step failed: {:?}", e);
+                            over = true;
+                            break;
+                        }
+                    }
+                }
+                if over {
+                    break;
+                }
+            }
+            "c" => {
+                match debugger.resume(Duration::from_secs(300)) {
+                    Ok(result) => println!("game over: \n{:#?}", result),
+                    Err(e) => eprintln!("resuming failed: {:?}", e),
+                }
+                break;
+            }
+            "q" => {
+                if let Err(e) = debugger.cancel() {
+                    eprintln!("cancelling failed: {:?}", e);
+                }
+                break;
+            }
+            other => eprintln!("unknown command '{}', use s [n], c or q", other),
+        }
+    }
+
+    for mut child in children {
+        let _ = child.wait();
+    }
+}
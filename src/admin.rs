@@ -0,0 +1,236 @@
+//! typed, blocking client for the admin side of the protocol
+//!
+//! every request waits for its response. the server does not acknowledge authenticate, pause
+//! and cancel, they return `Ack::Unconfirmed` if no error arrives within `ack_grace`.
+//! an error arriving later is not reported by them, but by the next request or `next_message`
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{AdminErr, ConnectionClosedErr, ReceiveErr},
+    internal::{AdminMessage, ComMessage, GameResult, Observed, PreparedRoom, RoomMessage},
+    season::{piranhas::Piranhas, GameType, Season},
+    socha_com::{ComHandler, PrepareSlot},
+};
+
+/// how a request without a response of its own was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ack {
+    /// a message confirmed the request, e.g. the `left` of a cancelled room
+    Confirmed,
+    /// no error arrived within `ack_grace`, the request may still fail later
+    Unconfirmed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paused {
+    pub room_id: String,
+    /// false if the room was resumed
+    pub paused: bool,
+    pub ack: Ack,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled {
+    pub room_id: String,
+    pub ack: Ack,
+}
+
+/// what happened after stepping a paused room
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome<S: Season = Piranhas> {
+    /// a move was played, contains the new state
+    Moved(Box<S::State>),
+    /// the step ended the game
+    GameOver(Box<GameResult>),
+}
+
+pub struct AdminClient<S: Season = Piranhas> {
+    com: ComHandler<S>,
+    /// messages that arrived while waiting for a response, but were not the response
+    pending: VecDeque<ComMessage<S>>,
+    /// how long to wait for a response
+    pub response_timeout: Duration,
+    /// how long to wait for an error, after requests the server does not acknowledge
+    pub ack_grace: Duration,
+}
+
+impl<S: Season> AdminClient<S> {
+    /// BLOCKING: connect to `addr`, `game_type` is used when preparing rooms
+    pub fn connect(addr: &str, game_type: &GameType) -> Result<Self, AdminErr> {
+        let com = ComHandler::connect_to_server_with_game_type(addr, game_type)?;
+        Ok(AdminClient {
+            com,
+            pending: VecDeque::new(),
+            response_timeout: Duration::from_secs(5),
+            ack_grace: Duration::from_millis(300),
+        })
+    }
+
+    /// BLOCKING: connect to `addr` and authenticate with `password`
+    /// a wrong password is only detected if the server rejects it within `ack_grace`,
+    /// see `authenticate`
+    pub fn connect_and_authenticate(
+        addr: &str,
+        game_type: &GameType,
+        password: &str,
+    ) -> Result<Self, AdminErr> {
+        let mut client = Self::connect(addr, game_type)?;
+        client.authenticate(password)?;
+        Ok(client)
+    }
+
+    /// the underlying connection
+    pub fn com(&mut self) -> &mut ComHandler<S> {
+        &mut self.com
+    }
+
+    /// BLOCKING: authenticate as admin
+    /// fails with `AdminErr::Server`, if the server rejects the password within `ack_grace`.
+    /// the server never confirms a correct password, so success is always `Ack::Unconfirmed`
+    pub fn authenticate(&mut self, password: &str) -> Result<Ack, AdminErr> {
+        self.com.send_admin_authenticate(password)?;
+        self.wait_for_ack(|_| false)
+    }
+
+    /// BLOCKING: prepare a new room and return its reservation codes
+    pub fn prepare(
+        &mut self,
+        pause: bool,
+        slots: &[PrepareSlot],
+    ) -> Result<PreparedRoom, AdminErr> {
+        self.com.send_admin_prepare(pause, slots)?;
+        self.wait_for_response(|msg| match msg {
+            ComMessage::Admin(AdminMessage::Prepared(prepared)) => Some(prepared.clone()),
+            _ => None,
+        })
+    }
+
+    /// BLOCKING: start observing a room
+    pub fn observe(&mut self, room_id: &str) -> Result<Observed, AdminErr> {
+        self.com.send_admin_observe(room_id)?;
+        self.wait_for_response(|msg| match msg {
+            ComMessage::Admin(AdminMessage::Observed(observed)) if observed.room_id == room_id => {
+                Some(observed.clone())
+            }
+            _ => None,
+        })
+    }
+
+    /// BLOCKING: pause or resume a room
+    /// the server does not confirm this, `Paused::ack` is always `Ack::Unconfirmed`:
+    /// no error arrived within `ack_grace`, one may still arrive later
+    pub fn pause(&mut self, room_id: &str, pause: bool) -> Result<Paused, AdminErr> {
+        self.com.send_admin_pause(room_id, pause)?;
+        let ack = self.wait_for_ack(|_| false)?;
+        Ok(Paused {
+            room_id: room_id.to_string(),
+            paused: pause,
+            ack,
+        })
+    }
+
+    /// BLOCKING: step a paused room by one move and wait for the resulting memento
+    /// the room has to be observed, otherwise no memento arrives
+    pub fn step(&mut self, room_id: &str) -> Result<StepOutcome<S>, AdminErr> {
+        self.com.send_admin_step(room_id)?;
+        self.wait_for_response(|msg| match msg {
+            ComMessage::Room { room_id: id, msg } if id == room_id => match &**msg {
+                RoomMessage::Memento(state) => Some(StepOutcome::Moved(state.clone())),
+                RoomMessage::Result(result) => Some(StepOutcome::GameOver(result.clone())),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// BLOCKING: cancel a room
+    /// `Cancelled::ack` is `Ack::Confirmed` once the room's `left` arrives within `ack_grace`,
+    /// `Ack::Unconfirmed` if neither it nor an error arrived in time
+    pub fn cancel(&mut self, room_id: &str) -> Result<Cancelled, AdminErr> {
+        self.com.send_admin_cancel(room_id)?;
+        let ack = self
+            .wait_for_ack(|msg| matches!(msg, ComMessage::Left(left) if left.room_id == room_id))?;
+        Ok(Cancelled {
+            room_id: room_id.to_string(),
+            ack,
+        })
+    }
+
+    /// BLOCKING: the next message, that was not the response to a request
+    /// e.g. mementos and results of observed rooms
+    pub fn next_message(&mut self, timeout: Duration) -> Result<ComMessage<S>, AdminErr> {
+        if let Some(msg) = self.pending.pop_front() {
+            return Ok(msg);
+        }
+        match self.com.wait_for_com_message(timeout) {
+            Ok(msg) => Ok(msg),
+            Err(ReceiveErr::ConnectionClosed(ConnectionClosedErr::NoMessageReceivedFor(t))) => {
+                Err(AdminErr::NoResponse(t))
+            }
+            Err(e) => Err(AdminErr::Receive(e)),
+        }
+    }
+
+    /// waits until `pick` accepts a message or the server sends an error
+    /// all other messages are kept for `next_message`
+    fn wait_for_response<T>(
+        &mut self,
+        mut pick: impl FnMut(&ComMessage<S>) -> Option<T>,
+    ) -> Result<T, AdminErr> {
+        let timeout = self.response_timeout;
+        let start_t = Instant::now();
+        loop {
+            let remaining = timeout.saturating_sub(start_t.elapsed());
+            if remaining.is_zero() {
+                return Err(AdminErr::NoResponse(timeout));
+            }
+            let msg = match self.com.wait_for_com_message(remaining) {
+                Ok(msg) => msg,
+                Err(ReceiveErr::ConnectionClosed(ConnectionClosedErr::NoMessageReceivedFor(_))) => {
+                    return Err(AdminErr::NoResponse(timeout))
+                }
+                Err(e) => return Err(AdminErr::Receive(e)),
+            };
+            if let ComMessage::Error(err) = msg {
+                return Err(AdminErr::Server(err));
+            }
+            if let Some(res) = pick(&msg) {
+                return Ok(res);
+            }
+            self.pending.push_back(msg);
+        }
+    }
+
+    /// waits `ack_grace` for an error, or until `confirms` accepts a message
+    fn wait_for_ack(
+        &mut self,
+        mut confirms: impl FnMut(&ComMessage<S>) -> bool,
+    ) -> Result<Ack, AdminErr> {
+        let start_t = Instant::now();
+        loop {
+            let remaining = self.ack_grace.saturating_sub(start_t.elapsed());
+            if remaining.is_zero() {
+                return Ok(Ack::Unconfirmed);
+            }
+            let msg = match self.com.wait_for_com_message(remaining) {
+                Ok(msg) => msg,
+                Err(ReceiveErr::ConnectionClosed(ConnectionClosedErr::NoMessageReceivedFor(_))) => {
+                    return Ok(Ack::Unconfirmed)
+                }
+                Err(e) => return Err(AdminErr::Receive(e)),
+            };
+            if let ComMessage::Error(err) = msg {
+                return Err(AdminErr::Server(err));
+            }
+            let confirmed = confirms(&msg);
+            self.pending.push_back(msg);
+            if confirmed {
+                return Ok(Ack::Confirmed);
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! step-through debugger for paused server games
+//!
+//! prepares a paused room, observes it and lets the caller play it one move at a time.
+//! every step yields a `DebugView` with the board, the legal moves of both teams and
+//! `GameState::evaluate`. `examples/step_debugger.rs` drives it from a terminal prompt
+
+use std::{fmt, time::Duration};
+
+use crate::{
+    admin::AdminClient,
+    error::AdminErr,
+    internal::{GameResult, GameState},
+    neutral::{Move, Team},
+    season::GameType,
+    socha_com::PrepareSlot,
+    spectator::{Spectator, SpectatorEvent},
+};
+
+/// everything shown for one position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugView {
+    pub state: GameState,
+    /// legal moves of team one, no matter whose turn it is
+    pub moves_one: Vec<Move>,
+    pub moves_two: Vec<Move>,
+    /// `GameState::evaluate` from the view of team one
+    pub eval: i32,
+}
+
+impl DebugView {
+    pub fn new(state: GameState) -> Self {
+        DebugView {
+            moves_one: state.possible_moves_for(Team::One),
+            moves_two: state.possible_moves_for(Team::Two),
+            eval: state.evaluate(Team::One),
+            state,
+        }
+    }
+
+    pub fn moves(&self, team: Team) -> &[Move] {
+        match team {
+            Team::One => &self.moves_one,
+            Team::Two => &self.moves_two,
+        }
+    }
+}
+
+impl fmt::Display for DebugView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = &self.state;
+        write!(f, "turn {}, {} to move", state.turn, state.current_team())?;
+        if let Some(mv) = state.last_move {
+            write!(f, ", last move {:?} {}", mv.from, mv.dir)?;
+        }
+        writeln!(f)?;
+        write!(f, "{}", state.board)?;
+        writeln!(
+            f,
+            "swarm size ONE {} / TWO {}, eval {:+} (for ONE)",
+            state.swarm_size(Team::One),
+            state.swarm_size(Team::Two),
+            self.eval
+        )?;
+        for team in [Team::One, Team::Two] {
+            let moves: Vec<String> = self
+                .moves(team)
+                .iter()
+                .map(|mv| format!("{:?} {}", mv.from, mv.dir))
+                .collect();
+            writeln!(f, "{} ({} moves): {}", team, moves.len(), moves.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// what happened after `StepDebugger::step`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugStep {
+    Moved(Box<DebugView>),
+    GameOver(Box<GameResult>),
+}
+
+/// a paused room, played one move at a time
+pub struct StepDebugger {
+    spectator: Spectator,
+    room_id: String,
+    /// (team one, team two)
+    reservations: (String, String),
+}
+
+impl StepDebugger {
+    /// BLOCKING: connects, authenticates and prepares a paused room with the slot names
+    /// `players`, which is observed from then on. the players join with `reservations`
+    pub fn prepare(
+        addr: &str,
+        game_type: &GameType,
+        password: &str,
+        players: (&str, &str),
+    ) -> Result<Self, AdminErr> {
+        let admin = AdminClient::connect_and_authenticate(addr, game_type, password)?;
+        Self::prepare_with(admin, players)
+    }
+
+    /// BLOCKING: like `prepare`, with an already authenticated admin client
+    pub fn prepare_with(admin: AdminClient, players: (&str, &str)) -> Result<Self, AdminErr> {
+        let mut spectator = Spectator::from_admin(admin);
+        let slots = [
+            PrepareSlot::new(players.0.to_string(), true, true),
+            PrepareSlot::new(players.1.to_string(), true, true),
+        ];
+        let prepared = spectator.admin().prepare(true, &slots)?;
+        spectator.observe(&prepared.room_id)?;
+        Ok(StepDebugger {
+            spectator,
+            room_id: prepared.room_id,
+            reservations: prepared.reservations,
+        })
+    }
+
+    pub fn room_id(&self) -> &str {
+        &self.room_id
+    }
+
+    /// reservation codes as (team one, team two)
+    pub fn reservations(&self) -> &(String, String) {
+        &self.reservations
+    }
+
+    /// BLOCKING: waits until both players joined and the start position arrived
+    pub fn wait_for_start(&mut self, timeout: Duration) -> Result<DebugView, AdminErr> {
+        if let Some(view) = self.view() {
+            return Ok(view);
+        }
+        loop {
+            match self.spectator.next_event(timeout)? {
+                SpectatorEvent::GameStarted { room_id, state } if room_id == self.room_id => {
+                    return Ok(DebugView::new(*state));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// BLOCKING: lets the current team make one move
+    pub fn step(&mut self) -> Result<DebugStep, AdminErr> {
+        match self.spectator.step(&self.room_id)? {
+            SpectatorEvent::GameFinished { result, .. } => Ok(DebugStep::GameOver(result)),
+            SpectatorEvent::GameStarted { state, .. }
+            | SpectatorEvent::MovePlayed { state, .. } => {
+                Ok(DebugStep::Moved(Box::new(DebugView::new(*state))))
+            }
+        }
+    }
+
+    /// the current position, none before the game started
+    pub fn view(&self) -> Option<DebugView> {
+        self.spectator
+            .room(&self.room_id)?
+            .latest()
+            .map(|state| DebugView::new(state.clone()))
+    }
+
+    /// every position so far, the first one is the start position
+    pub fn history(&self) -> &[GameState] {
+        self.spectator
+            .room(&self.room_id)
+            .map_or(&[], |timeline| timeline.states.as_slice())
+    }
+
+    /// BLOCKING: unpauses the room, the rest of the game is played without stepping
+    /// returns the result once it arrived
+    pub fn resume(&mut self, timeout: Duration) -> Result<GameResult, AdminErr> {
+        self.spectator.admin().pause(&self.room_id, false)?;
+        loop {
+            match self.spectator.next_event(timeout)? {
+                SpectatorEvent::GameFinished { room_id, result } if room_id == self.room_id => {
+                    return Ok(*result);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// BLOCKING: cancels the room
+    pub fn cancel(&mut self) -> Result<(), AdminErr> {
+        self.spectator.admin().cancel(&self.room_id)?;
+        Ok(())
+    }
+}
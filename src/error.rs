@@ -1,8 +1,15 @@
-use std::io;
+use std::{fmt, io};
+
+use crate::{
+    internal::{FieldDiff, ServerError},
+    neutral::Move,
+    season::GameType,
+};
 
 #[derive(Debug)]
 pub enum ComMessageBuildErr {
     FailedBuildingMemento(String),
+    FailedBuildingServerError(String),
 }
 
 #[derive(Debug)]
@@ -19,6 +26,25 @@ pub enum ReceiveErr {
     ConnectionClosed(ConnectionClosedErr),
     FailedToBuildRoomMessage(String),
     FailedToBuildAdminMessage(String),
+    /// the connection dropped and could not be restored
+    RejoinFailed(RejoinErr),
+    /// the client runtime does not play this game
+    UnsupportedGameType(GameType),
+    /// the thread reading the connection panicked, contains the panic message
+    ReaderPanicked(String),
+}
+
+#[derive(Debug)]
+pub enum RejoinErr {
+    /// only rooms joined with a reservation code can be rejoined
+    NoReservationCode,
+    /// the server refused to let us back into the room
+    Rejected(ServerError),
+    /// every reconnect attempt failed, contains the error of the last attempt
+    GaveUp {
+        attempts: u32,
+        last_err: Option<Box<ReceiveErr>>,
+    },
 }
 
 impl From<io::Error> for ReceiveErr {
@@ -35,6 +61,8 @@ impl From<strong_xml::XmlError> for ReceiveErr {
 #[derive(Debug)]
 pub enum SendErr {
     NoRoomId,
+    /// the connection was already closed with `ComHandler::close`
+    ConnectionClosed,
     FailedToBuildXml,
     Io(io::Error),
 }
@@ -62,3 +90,167 @@ impl From<ReceiveErr> for ComError {
         ComError::ReceiveErr(value)
     }
 }
+
+/// error of a request made with `crate::admin::AdminClient`
+#[derive(Debug)]
+pub enum AdminErr {
+    /// the server answered the request with an error packet
+    Server(ServerError),
+    Send(SendErr),
+    Receive(ReceiveErr),
+    /// the server did not answer in time
+    NoResponse(std::time::Duration),
+}
+
+impl From<SendErr> for AdminErr {
+    fn from(value: SendErr) -> Self {
+        AdminErr::Send(value)
+    }
+}
+
+impl From<ReceiveErr> for AdminErr {
+    fn from(value: ReceiveErr) -> Self {
+        AdminErr::Receive(value)
+    }
+}
+
+/// error of a match run by `crate::orchestrator::Orchestrator`
+#[cfg(feature = "tools")]
+#[derive(Debug)]
+pub enum OrchestratorErr {
+    Admin(AdminErr),
+    /// a bot executable could not be started
+    SpawnBot {
+        program: String,
+        err: io::Error,
+    },
+    /// the address could not be split into host and port
+    InvalidAddr(String),
+    /// no result arrived for the room within the game timeout
+    GameTimeout {
+        room_id: String,
+    },
+}
+
+#[cfg(feature = "tools")]
+impl From<AdminErr> for OrchestratorErr {
+    fn from(value: AdminErr) -> Self {
+        OrchestratorErr::Admin(value)
+    }
+}
+
+/// invalid command line arguments, see `crate::i_client_handler::args::ClientArgs`
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgsErr {
+    /// the flag was the last argument
+    MissingValue(String),
+    InvalidPort(String),
+    InvalidLogLevel(String),
+    InvalidGameType(String),
+    /// a valid game type the client runtime does not play
+    UnsupportedGameType(String),
+}
+
+impl fmt::Display for ArgsErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsErr::MissingValue(flag) => write!(f, "missing value for '{}'", flag),
+            ArgsErr::InvalidPort(port) => write!(f, "invalid port '{}'", port),
+            ArgsErr::InvalidLogLevel(level) => write!(f, "invalid log level '{}'", level),
+            ArgsErr::InvalidGameType(game_type) => {
+                write!(f, "invalid game type '{}'", game_type)
+            }
+            ArgsErr::UnsupportedGameType(game_type) => {
+                write!(f, "game type '{}' is not played by this client", game_type)
+            }
+        }
+    }
+}
+
+/// error of `crate::i_client_handler::args::run_from_args`
+#[derive(Debug)]
+pub enum RunErr {
+    Args(ArgsErr),
+    /// the log file could not be created
+    Log(io::Error),
+    Receive(ReceiveErr),
+}
+
+impl From<ArgsErr> for RunErr {
+    fn from(value: ArgsErr) -> Self {
+        RunErr::Args(value)
+    }
+}
+
+impl From<ReceiveErr> for RunErr {
+    fn from(value: ReceiveErr) -> Self {
+        RunErr::Receive(value)
+    }
+}
+
+/// error of `crate::package`
+#[cfg(feature = "tools")]
+#[derive(Debug)]
+pub enum PackageErr {
+    /// building the release executable failed
+    Build(String),
+    Io(io::Error),
+    MissingFile(std::path::PathBuf),
+    /// the zip does not have the layout the contest system expects
+    InvalidPackage(String),
+    /// the named part exceeds a limit of the zip format
+    TooLarge(String),
+    /// writing the zip failed
+    Zip(zip::result::ZipError),
+    Orchestrator(OrchestratorErr),
+    /// the packaged bot did not play a clean game on the mock server
+    Verify(String),
+}
+
+#[cfg(feature = "tools")]
+impl From<io::Error> for PackageErr {
+    fn from(value: io::Error) -> Self {
+        PackageErr::Io(value)
+    }
+}
+
+#[cfg(feature = "tools")]
+impl From<zip::result::ZipError> for PackageErr {
+    fn from(value: zip::result::ZipError) -> Self {
+        PackageErr::Zip(value)
+    }
+}
+
+#[cfg(feature = "tools")]
+impl From<OrchestratorErr> for PackageErr {
+    fn from(value: OrchestratorErr) -> Self {
+        PackageErr::Orchestrator(value)
+    }
+}
+
+/// error type of the fallible `IClientHandler` methods
+pub type HandlerErr = Box<dyn std::error::Error + Send + Sync>;
+
+/// handed to `IClientHandler::on_error`
+#[derive(Debug)]
+pub enum ClientErr<'a> {
+    /// `calculate_move` returned an error
+    CalculateMove(HandlerErr),
+    /// `calculate_move` panicked, with the panic message
+    CalculateMovePanicked(String),
+    /// a move or command could not be sent
+    Send(SendErr),
+    /// the connection failed and could not be recovered, `start_iclient` returns this error
+    Receive(&'a ReceiveErr),
+}
+
+/// error of `crate::internal::GameState::infer_move`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InferMoveErr {
+    /// both boards are the same
+    Unchanged,
+    /// no legal move leads to the second board, with the squares that changed
+    NoLegalMove(Vec<FieldDiff>),
+    /// several legal moves lead to the second board
+    Ambiguous(Vec<Move>),
+}
@@ -0,0 +1,66 @@
+use log::info;
+
+use crate::{
+    error::AdminErr,
+    i_admin_handler::AdminCommandSender,
+    i_client_handler::handler_trait::HandlerControl,
+    internal::{GameResult, GameState, PreparedRoom, ServerError},
+};
+
+/// the admin side of `crate::i_client_handler::handler_trait::IClientHandler`,
+/// run by `crate::i_admin_handler::start_admin`
+/// an admin connection is never ended by the server, a callback has to return
+/// `HandlerControl::Leave` or `HandlerControl::Stop` at some point
+pub trait IAdminHandler {
+    /// called once the password counts as accepted, before any message is handled
+    /// keep `commands` to prepare, observe, pause, step and cancel rooms from other callbacks
+    /// or from other threads
+    #[allow(unused_variables)]
+    fn on_authenticated(&mut self, commands: AdminCommandSender) -> HandlerControl {
+        info!("authenticated as admin");
+        HandlerControl::Continue
+    }
+
+    /// called when a room was prepared, `prepared.reservations` are the codes for the players
+    #[allow(unused_variables)]
+    fn on_prepared(&mut self, prepared: &PreparedRoom) -> HandlerControl {
+        HandlerControl::Continue
+    }
+
+    /// called when the server confirmed observing `room_id`
+    #[allow(unused_variables)]
+    fn on_observed(&mut self, room_id: &str) -> HandlerControl {
+        HandlerControl::Continue
+    }
+
+    /// called for every memento of an observed room
+    #[allow(unused_variables)]
+    fn on_room_state(&mut self, room_id: &str, state: &GameState) -> HandlerControl {
+        HandlerControl::Continue
+    }
+
+    /// called when the result of an observed room arrived
+    #[allow(unused_variables)]
+    fn on_room_result(&mut self, room_id: &str, result: &GameResult) -> HandlerControl {
+        HandlerControl::Continue
+    }
+
+    /// called when the server closed `room_id`, e.g. after it was cancelled
+    #[allow(unused_variables)]
+    fn on_room_left(&mut self, room_id: &str) -> HandlerControl {
+        HandlerControl::Continue
+    }
+
+    /// called when the server sent an error packet, e.g. for an unknown room id
+    /// a wrong password is not reported here, `start_admin` returns it as `AdminErr::Server`
+    fn on_server_error(&mut self, err: &ServerError) -> HandlerControl {
+        info!("server error: {}", err);
+        HandlerControl::Continue
+    }
+
+    /// called when the connection failed, `start_admin` returns `err` right after
+    /// commands that could not be sent are reported by `AdminCommandSender` itself
+    fn on_error(&mut self, err: &AdminErr) {
+        info!("admin error: {:?}", err);
+    }
+}
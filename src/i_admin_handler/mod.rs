@@ -0,0 +1,209 @@
+//! callback based runtime for the admin side of the protocol, parallel to `crate::i_client_handler`
+//!
+//! `start_admin` drives a `crate::admin::AdminClient`: it authenticates, then hands every admin
+//! response and every message of observed rooms to an `IAdminHandler`. commands are written
+//! by an `AdminCommandSender` the moment they are issued, from callbacks or from other threads
+
+use std::time::{Duration, Instant};
+
+use log::info;
+pub mod handler_trait;
+use crate::{
+    admin::AdminClient,
+    error::{AdminErr, ConnectionClosedErr, ReceiveErr, SendErr},
+    i_admin_handler::handler_trait::IAdminHandler,
+    i_client_handler::{handler_trait::HandlerControl, ClientEnd, LEAVE_TIMEOUT},
+    internal::{AdminMessage, ComMessage, GameResult, PreparedRoom, RoomMessage},
+    season::{piranhas::Piranhas, GameType, Season},
+    socha_com::{ComSender, PrepareSlot},
+};
+
+/// how long `start_admin` waits for a message before it waits again,
+/// an admin connection is silent while no room is observed
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+/// sends admin commands for an `IAdminHandler`, straight to the socket of its connection
+/// handed to the handler through `IAdminHandler::on_authenticated`, can be cloned into other threads
+#[derive(Clone)]
+pub struct AdminCommandSender {
+    sender: ComSender,
+}
+
+impl AdminCommandSender {
+    /// answered by `IAdminHandler::on_prepared`
+    pub fn prepare(&self, pause: bool, slots: &[PrepareSlot]) -> Result<(), SendErr> {
+        self.sender.send_admin_prepare(pause, slots)
+    }
+
+    /// answered by `IAdminHandler::on_observed`
+    pub fn observe(&self, room_id: &str) -> Result<(), SendErr> {
+        self.sender.send_admin_observe(room_id)
+    }
+
+    /// pause or resume a room
+    pub fn pause(&self, room_id: &str, pause: bool) -> Result<(), SendErr> {
+        self.sender.send_admin_pause(room_id, pause)
+    }
+
+    /// steps a paused room by one move, the memento arrives in `IAdminHandler::on_room_state`
+    /// if the room is observed
+    pub fn step(&self, room_id: &str) -> Result<(), SendErr> {
+        self.sender.send_admin_step(room_id)
+    }
+
+    pub fn cancel(&self, room_id: &str) -> Result<(), SendErr> {
+        self.sender.send_admin_cancel(room_id)
+    }
+}
+
+/// everything `start_admin` needs to connect and authenticate
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    pub addr: String,
+    pub game_type: GameType,
+    pub password: String,
+    /// the server does not acknowledge the password, it counts as accepted
+    /// if no error arrives within this time
+    pub ack_grace: Duration,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        AdminConfig {
+            addr: "localhost:13050".to_string(),
+            game_type: Piranhas::game_type(),
+            password: "examplepassword".to_string(),
+            ack_grace: Duration::from_millis(300),
+        }
+    }
+}
+
+/// what `start_admin` returns once the handler is done
+#[derive(Debug, Clone, Default)]
+pub struct AdminSummary {
+    /// every room prepared over this connection
+    pub prepared: Vec<PreparedRoom>,
+    /// room id and result of every observed room that finished
+    pub results: Vec<(String, GameResult)>,
+    pub end: ClientEnd,
+}
+
+/// BLOCKING: connects, authenticates and runs `i_admin_handler` until a callback returns
+/// `HandlerControl::Leave` or `HandlerControl::Stop`, or the server ends the protocol
+/// the password is checked by `AdminClient::authenticate` before any callback runs, so an error
+/// within `ack_grace` can only be its answer and `start_admin` fails with `AdminErr::Server`.
+/// messages arriving meanwhile are dispatched after `IAdminHandler::on_authenticated`
+pub fn start_admin<H>(
+    config: &AdminConfig,
+    i_admin_handler: &mut H,
+) -> Result<AdminSummary, AdminErr>
+where
+    H: IAdminHandler,
+{
+    let mut admin: AdminClient = AdminClient::connect(&config.addr, &config.game_type)?;
+    admin.ack_grace = config.ack_grace;
+    let sender = admin.com().sender();
+    if let Err(e) = admin.authenticate(&config.password) {
+        info!("authentication failed: {:?}", e);
+        sender.shutdown();
+        return Err(e);
+    }
+
+    let mut summary = AdminSummary::default();
+    // set while leaving, the connection is shut down if the server takes longer
+    let mut leave_deadline: Option<Instant> = None;
+    let mut control = i_admin_handler.on_authenticated(AdminCommandSender {
+        sender: sender.clone(),
+    });
+    loop {
+        match control {
+            HandlerControl::Continue => {}
+            HandlerControl::Leave => {
+                if leave_deadline.is_none() {
+                    info!("admin handler is leaving");
+                    summary.end = ClientEnd::HandlerLeft;
+                    if let Err(e) = sender.send_close() {
+                        info!("sending close failed: {:?}", e);
+                    }
+                    leave_deadline = Some(Instant::now() + LEAVE_TIMEOUT);
+                }
+            }
+            HandlerControl::Stop => {
+                info!("admin handler stopped");
+                summary.end = ClientEnd::HandlerStopped;
+                sender.shutdown();
+                return Ok(summary);
+            }
+        }
+        let wait = match leave_deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => IDLE_WAIT,
+        };
+        match admin.next_message(wait) {
+            // only the end of the protocol is waited for
+            Ok(_) if leave_deadline.is_some() => {}
+            Ok(msg) => control = dispatch(i_admin_handler, &mut summary, msg),
+            Err(AdminErr::NoResponse(_)) if leave_deadline.is_some() => {
+                info!("server did not end the protocol after close, shutting down");
+                sender.shutdown();
+                return Ok(summary);
+            }
+            Err(AdminErr::NoResponse(_)) => {}
+            Err(AdminErr::Receive(ReceiveErr::ConnectionClosed(
+                ConnectionClosedErr::ProtocolEnded,
+            ))) => return Ok(summary),
+            // closed on purpose by leaving
+            Err(_) if leave_deadline.is_some() => return Ok(summary),
+            Err(e) => {
+                i_admin_handler.on_error(&e);
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// hands one message to the matching callback and keeps `summary` up to date
+fn dispatch<H>(
+    i_admin_handler: &mut H,
+    summary: &mut AdminSummary,
+    msg: ComMessage,
+) -> HandlerControl
+where
+    H: IAdminHandler,
+{
+    match msg {
+        ComMessage::Admin(AdminMessage::Prepared(prepared)) => {
+            info!("room {} prepared", prepared.room_id);
+            let control = i_admin_handler.on_prepared(&prepared);
+            summary.prepared.push(prepared);
+            control
+        }
+        ComMessage::Admin(AdminMessage::Observed(observed)) => {
+            info!("observing room {}", observed.room_id);
+            i_admin_handler.on_observed(&observed.room_id)
+        }
+        ComMessage::Room { room_id, msg } => match *msg {
+            RoomMessage::Memento(state) => i_admin_handler.on_room_state(&room_id, &state),
+            RoomMessage::Result(result) => {
+                info!("room {} finished", room_id);
+                let control = i_admin_handler.on_room_result(&room_id, &result);
+                summary.results.push((room_id, *result));
+                control
+            }
+            // only sent to players
+            RoomMessage::WelcomeMessage(_) | RoomMessage::MoveRequest => HandlerControl::Continue,
+        },
+        ComMessage::Left(left) => {
+            info!("room {} closed", left.room_id);
+            i_admin_handler.on_room_left(&left.room_id)
+        }
+        ComMessage::Joined(joined) => {
+            info!("ignoring joined for room {}", joined.room_id);
+            HandlerControl::Continue
+        }
+        ComMessage::Error(err) => {
+            info!("got server error: {}", err);
+            i_admin_handler.on_server_error(&err)
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{path::PathBuf, str::FromStr};
+
+use log::{info, LevelFilter};
+
+use crate::{
+    error::{ArgsErr, RunErr},
+    i_client_handler::{
+        handler_trait::IClientHandler, start_iclient_with_config, supports_game_type, ClientConfig,
+        ClientSummary,
+    },
+    season::{piranhas::Piranhas, GameType, Season},
+};
+
+/// the arguments the contest system passes to every client
+/// `--host <host> --port <port> --reservation <code>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientArgs {
+    pub host: String,
+    pub port: u16,
+    pub reservation: Option<String>,
+    /// the game to join when there is no reservation code, only games the client runtime plays
+    /// are accepted
+    pub game_type: GameType,
+    /// none disables logging
+    pub log_file: Option<PathBuf>,
+    pub log_level: LevelFilter,
+    /// arguments that were not understood, they are ignored instead of failing the start
+    pub unknown: Vec<String>,
+}
+
+impl Default for ClientArgs {
+    fn default() -> Self {
+        ClientArgs {
+            host: "localhost".to_string(),
+            port: 13050,
+            reservation: None,
+            game_type: Piranhas::game_type(),
+            log_file: Some(PathBuf::from("socha_client.log")),
+            log_level: LevelFilter::Info,
+            unknown: Vec::new(),
+        }
+    }
+}
+
+impl ClientArgs {
+    pub const USAGE: &'static str = "usage: [--host|-h <host>] [--port|-p <port>] [--reservation|-r <code>] [--game-type|-g <swc_year_game>] [--log-file <path>] [--log-level <level>] [--no-log]";
+
+    /// parses the arguments of the current process
+    pub fn from_env() -> Result<Self, ArgsErr> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// parses `args`, without the program name
+    /// flags are accepted as `--flag value` and `--flag=value`
+    pub fn parse<I, A>(args: I) -> Result<Self, ArgsErr>
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        let mut parsed = ClientArgs::default();
+        let mut args = args.into_iter().map(Into::into);
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with('-') => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = |flag: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ArgsErr::MissingValue(flag.to_string()))
+            };
+            match flag.as_str() {
+                "--host" | "-h" => parsed.host = value(&flag)?,
+                "--port" | "-p" => {
+                    let port = value(&flag)?;
+                    parsed.port = port
+                        .trim()
+                        .parse()
+                        .map_err(|_| ArgsErr::InvalidPort(port))?;
+                }
+                "--reservation" | "-r" => parsed.reservation = Some(value(&flag)?),
+                "--game-type" | "-g" => {
+                    let game_type = value(&flag)?;
+                    parsed.game_type = GameType::from_str(&game_type)
+                        .map_err(|_| ArgsErr::InvalidGameType(game_type.clone()))?;
+                    if !supports_game_type(&parsed.game_type) {
+                        return Err(ArgsErr::UnsupportedGameType(game_type));
+                    }
+                }
+                "--log-file" => parsed.log_file = Some(PathBuf::from(value(&flag)?)),
+                "--log-level" => {
+                    let level = value(&flag)?;
+                    parsed.log_level = LevelFilter::from_str(&level)
+                        .map_err(|_| ArgsErr::InvalidLogLevel(level))?;
+                }
+                "--no-log" => parsed.log_file = None,
+                _ => parsed.unknown.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// `host:port`, an IPv6 host is put in brackets, e.g. `[::1]:13050`
+    pub fn addr(&self) -> String {
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// a `ClientConfig` with the defaults for everything not set by arguments
+    pub fn to_config(&self) -> ClientConfig {
+        ClientConfig {
+            addr: self.addr(),
+            reservation_code: self.reservation.clone(),
+            game_type: self.game_type.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// entry point for bots started by the contest system
+/// parses the standard arguments, logs into the log file and runs `i_client_handler`
+/// on invalid arguments the usage is printed to stderr
+pub fn run_from_args<I>(i_client_handler: &mut I) -> Result<ClientSummary, RunErr>
+where
+    I: IClientHandler,
+{
+    let args = ClientArgs::from_env().inspect_err(|e| {
+        eprintln!("{}", e);
+        eprintln!("{}", ClientArgs::USAGE);
+    })?;
+    run_with_args(&args, i_client_handler)
+}
+
+/// like `run_from_args`, with already parsed arguments
+pub fn run_with_args<I>(
+    args: &ClientArgs,
+    i_client_handler: &mut I,
+) -> Result<ClientSummary, RunErr>
+where
+    I: IClientHandler,
+{
+    if let Some(path) = &args.log_file {
+        simple_logging::log_to_file(path, args.log_level).map_err(RunErr::Log)?;
+    }
+    for unknown in &args.unknown {
+        info!("ignoring unknown argument '{}'", unknown);
+    }
+    info!(
+        "starting client for {}, reservation {:?}",
+        args.addr(),
+        args.reservation
+    );
+    let summary = start_iclient_with_config(&args.to_config(), i_client_handler)?;
+    info!("client finished: {:?}", summary.end);
+    Ok(summary)
+}
@@ -0,0 +1,125 @@
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// why a `CancelToken` was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelReason {
+    /// the server requested our next move
+    MoveRequest,
+    /// the game result arrived
+    GameOver,
+    /// we are not in the room anymore
+    RoomLeft,
+    /// the time given to `while_waiting` is over
+    Timeout,
+    /// the connection is gone
+    Disconnected,
+    /// cancelled by the handler itself
+    Manual,
+}
+
+/// tells `IClientHandler::while_waiting`, and every search thread it hands a clone to, when to stop
+/// cancelled by the runtime as soon as the reason arrives, only the first reason is kept
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Debug, Default)]
+struct TokenInner {
+    state: Mutex<TokenState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    reason: Option<CancelReason>,
+    /// cancels with `CancelReason::Timeout` once reached
+    deadline: Option<Instant>,
+}
+
+impl TokenState {
+    fn check(&mut self) -> Option<CancelReason> {
+        if self.reason.is_none() && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.reason = Some(CancelReason::Timeout);
+        }
+        self.reason
+    }
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TokenState> {
+        self.inner.state.lock().unwrap()
+    }
+
+    /// cancels the token, returns false if it was cancelled before
+    pub fn cancel(&self, reason: CancelReason) -> bool {
+        let mut state = self.lock();
+        if state.check().is_some() {
+            return false;
+        }
+        state.reason = Some(reason);
+        self.inner.changed.notify_all();
+        true
+    }
+
+    /// cancels with `CancelReason::Timeout` after `timeout`, unless cancelled before
+    pub fn cancel_after(&self, timeout: Duration) {
+        self.lock().deadline = Some(Instant::now() + timeout);
+        self.inner.changed.notify_all();
+    }
+
+    pub fn reason(&self) -> Option<CancelReason> {
+        self.lock().check()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.reason().is_some()
+    }
+
+    /// BLOCKING: waits until the token is cancelled
+    pub fn wait(&self) -> CancelReason {
+        let mut state = self.lock();
+        loop {
+            if let Some(reason) = state.check() {
+                return reason;
+            }
+            state = match state.deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    self.inner.changed.wait_timeout(state, remaining).unwrap().0
+                }
+                None => self.inner.changed.wait(state).unwrap(),
+            };
+        }
+    }
+
+    /// BLOCKING: waits at most `timeout` for the token to be cancelled
+    /// returns none if it is still not cancelled
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<CancelReason> {
+        let until = Instant::now() + timeout;
+        let mut state = self.lock();
+        loop {
+            if let Some(reason) = state.check() {
+                return Some(reason);
+            }
+            let now = Instant::now();
+            if now >= until {
+                return None;
+            }
+            let wake = state.deadline.map_or(until, |deadline| deadline.min(until));
+            state = self
+                .inner
+                .changed
+                .wait_timeout(state, wake.saturating_duration_since(now))
+                .unwrap()
+                .0;
+        }
+    }
+}
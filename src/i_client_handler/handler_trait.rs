@@ -1,60 +1,116 @@
-use std::process::exit;
-
 use log::info;
 
 use crate::{
-    i_client_handler::ComCancelHandler,
-    internal::{GameResult, GameState, PreparedRoom},
+    error::{ClientErr, HandlerErr},
+    i_client_handler::{
+        cancel::CancelToken, move_context::MoveContext, state_tracker::Desync, CommandSender,
+    },
+    internal::{GameResult, GameState, ServerError, WelcomeMessage},
     neutral::Move,
 };
 
-// todo: add on error
+/// returned by the callbacks of `IClientHandler`, tells the runtime how to go on
+/// ordered from mild to drastic, `max` picks the more drastic one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum HandlerControl {
+    /// keep handling messages, until the server ends the protocol
+    #[default]
+    Continue,
+    /// close the connection politely, wait for the server to end the protocol and return
+    Leave,
+    /// return right away, the connection is shut down without waiting for the server
+    Stop,
+}
+
 pub trait IClientHandler {
     /// called when a move was requested by the server
-    fn calculate_move(&mut self) -> Move;
+    /// the move has to be returned before `ctx.deadline`, otherwise the game is lost
+    /// errors and panics are handled by `ClientConfig::recovery` and reported to `on_error`
+    fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr>;
 
     /// called when the server sends a game_state_update
     /// this function is used to save the current gamestate into the struct, that implements IClientHandler
-    fn on_gamestate_update(&mut self, state: GameState);
+    fn on_gamestate_update(&mut self, state: GameState) -> HandlerControl;
+
+    /// called before `on_gamestate_update`, if the new memento does not match the previous
+    /// state with the last move applied locally. the server's state is used from then on
+    fn on_desync(&mut self, desync: &Desync) -> HandlerControl {
+        info!("{}", desync);
+        HandlerControl::Continue
+    }
+
+    /// called before `on_gamestate_update`, when a new memento shows the opponent's move `mv`
+    /// `mv` is the memento's `last_move`, or inferred from the boards if it is missing
+    #[allow(unused_variables)]
+    fn on_opponent_move(
+        &mut self,
+        mv: Move,
+        before: &GameState,
+        after: &GameState,
+    ) -> HandlerControl {
+        HandlerControl::Continue
+    }
+
+    /// called once after connecting, before any message is handled
+    /// keep `commands` to send moves or raw xml from other callbacks
+    #[allow(unused_variables)]
+    fn on_connected(&mut self, commands: CommandSender) -> HandlerControl {
+        HandlerControl::Continue
+    }
 
     /// called when the client has successfully joined a room
-    fn on_game_joined(&mut self, room_id: &str) {
+    fn on_game_joined(&mut self, room_id: &str) -> HandlerControl {
         println!("joined game with id: {}", room_id);
+        HandlerControl::Continue
     }
 
     /// called when client left the room
-    fn on_game_left(&mut self) {
+    fn on_game_left(&mut self) -> HandlerControl {
         info!("client is not in game room anymore");
+        HandlerControl::Continue
     }
 
     /// called when the result of the current game has been received
-    fn on_game_result(&mut self, res: &GameResult) {
-        println!("game over, exiting");
+    /// the result is also part of the summary returned by `start_iclient`
+    fn on_game_result(&mut self, res: &GameResult) -> HandlerControl {
+        println!("game over");
         println!("final result: \n{:#?}", res);
-        exit(0)
+        HandlerControl::Continue
     }
 
     /// called when the welcome message was received from the server
-    fn on_welcome_message(&mut self) {
-        info!("received welcome")
+    /// `welcome.team` is the team this client plays as, none if the server did not say
+    fn on_welcome_message(&mut self, welcome: &WelcomeMessage) -> HandlerControl {
+        info!("received welcome, playing as team {:?}", welcome.team);
+        HandlerControl::Continue
     }
 
-    /// is ran, while the enemy is calculating their move
-    /// if cancel_handler.is_canceled() returns true, the while waiting function should return as soon as possible
-    #[allow(unused_variables)]
-    fn while_waiting(&mut self, cancel_handler: ComCancelHandler) {}
+    /// called when the server sent an error packet, e.g. after an invalid move
+    /// or a wrong reservation code
+    fn on_server_error(&mut self, err: &ServerError) -> HandlerControl {
+        info!("server error: {}", err);
+        HandlerControl::Continue
+    }
 
-    /// ADMIN
-    /// called when a game is prepared
-    #[allow(unused_variables)]
-    fn on_game_prepared(&mut self, prepared: &PreparedRoom) {}
+    /// called after the runtime recovered from `err` as `ClientConfig::recovery` says
+    /// for `ClientErr::Receive` the returned control is ignored, the client ends anyway
+    fn on_error(&mut self, err: &ClientErr) -> HandlerControl {
+        info!("client error: {:?}", err);
+        HandlerControl::Continue
+    }
 
-    /// ADMIN
-    /// when a game was created
-    fn on_create_game(&mut self) {}
+    /// called when the connection dropped and the client rejoined the room
+    /// the game state is resynchronised by the next memento, which calls `on_gamestate_update`
+    fn on_reconnected(&mut self) -> HandlerControl {
+        info!("reconnected, waiting for the next memento");
+        HandlerControl::Continue
+    }
 
-    /// ADMIN
-    /// called when client starts observing a room
+    /// is ran, while the enemy is calculating their move
+    /// only called after a move was sent, not if no move was found or an interceptor dropped it
+    /// `state` is the position after our move, see `crate::i_client_handler::ponder` to search it
+    /// once `cancel` is cancelled (next move request, game over, timeout, ...) the function
+    /// should return as soon as possible. clones of `cancel` can be handed to search threads
     #[allow(unused_variables)]
-    fn on_observed(&mut self, room_id: &str) {}
+    fn while_waiting(&mut self, state: &GameState, cancel: CancelToken) {}
 }
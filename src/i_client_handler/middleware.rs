@@ -0,0 +1,139 @@
+//! interceptors around the client runtime
+//!
+//! every message from the server passes the inbound side of a `Middleware` before it is
+//! dispatched, every command (moves included) passes the outbound side before it is sent.
+//! an interceptor can look at, record, change or drop (veto) each of them, without touching
+//! the `IClientHandler`. interceptors run on the runtime's threads, state that should outlive
+//! `start_iclient_with_middleware` is best shared through an `Arc`
+
+use std::{fmt, time::Instant};
+
+use log::info;
+
+use crate::{
+    i_client_handler::SendCommnad,
+    internal::{ComMessage, GameState, RoomMessage},
+};
+
+pub trait Interceptor: Send {
+    /// called for every message from the server, before it is dispatched
+    /// returns the message, changed or not, or none to drop it
+    #[allow(unused_variables)]
+    fn inbound(&mut self, msg: ComMessage, received_at: Instant) -> Option<ComMessage> {
+        Some(msg)
+    }
+
+    /// called for every command, before it is sent
+    /// returns the command, changed or not, or none to drop it
+    fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+        Some(cmd)
+    }
+}
+
+/// a chain of interceptors, the first one added is the outermost:
+/// it sees inbound messages first and outbound commands last
+#[derive(Default)]
+pub struct Middleware {
+    interceptors: Vec<Box<dyn Interceptor>>,
+}
+
+impl fmt::Debug for Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Middleware")
+            .field("interceptors", &self.interceptors.len())
+            .finish()
+    }
+}
+
+impl Middleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds `interceptor` inside of all interceptors added before
+    pub fn with(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.push(interceptor);
+        self
+    }
+
+    pub fn push(&mut self, interceptor: impl Interceptor + 'static) {
+        self.interceptors.push(Box::new(interceptor));
+    }
+
+    pub fn len(&self) -> usize {
+        self.interceptors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    /// runs `msg` through all interceptors, none if one of them dropped it
+    pub fn inbound(&mut self, msg: ComMessage, received_at: Instant) -> Option<ComMessage> {
+        self.interceptors
+            .iter_mut()
+            .try_fold(msg, |msg, interceptor| {
+                interceptor.inbound(msg, received_at)
+            })
+    }
+
+    /// runs `cmd` through all interceptors, innermost first, none if one of them dropped it
+    pub fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+        self.interceptors
+            .iter_mut()
+            .rev()
+            .try_fold(cmd, |cmd, interceptor| interceptor.outbound(cmd))
+    }
+}
+
+/// logs all traffic with `log::info`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessageLog;
+
+impl Interceptor for MessageLog {
+    fn inbound(&mut self, msg: ComMessage, _received_at: Instant) -> Option<ComMessage> {
+        info!("<- {:?}", msg);
+        Some(msg)
+    }
+
+    fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+        info!("-> {:?}", cmd);
+        Some(cmd)
+    }
+}
+
+/// checks every move against the last memento before it is sent
+/// an illegal move is replaced by the first legal one, or dropped if there is none
+#[derive(Debug, Clone, Default)]
+pub struct MoveGuard {
+    state: Option<GameState>,
+}
+
+impl MoveGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Interceptor for MoveGuard {
+    fn inbound(&mut self, msg: ComMessage, _received_at: Instant) -> Option<ComMessage> {
+        if let ComMessage::Room { msg: room_msg, .. } = &msg {
+            if let RoomMessage::Memento(state) = &**room_msg {
+                self.state = Some((**state).clone());
+            }
+        }
+        Some(msg)
+    }
+
+    fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+        let (SendCommnad::Move(mv), Some(state)) = (&cmd, &self.state) else {
+            return Some(cmd);
+        };
+        let possible_moves = state.possible_moves();
+        if possible_moves.contains(mv) {
+            return Some(cmd);
+        }
+        info!("move guard: {:?} is illegal in turn {}", mv, state.turn);
+        possible_moves.first().map(|mv| SendCommnad::Move(*mv))
+    }
+}
@@ -1,230 +1,825 @@
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
 use std::time::Duration;
 use std::{
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    thread::{self, JoinHandle},
+    sync::{Arc, Mutex},
     time::Instant,
 };
 
+use crossbeam_channel::{after, bounded, never, select, unbounded, Sender};
 use log::info;
+pub mod args;
+pub mod cancel;
 pub mod handler_trait;
-use crate::{error::ReceiveErr, i_client_handler::handler_trait::IClientHandler};
+pub mod middleware;
+pub mod move_context;
+pub mod ponder;
+pub mod script;
+pub mod state_tracker;
 use crate::{
-    internal::{ComMessage, RoomMessage},
-    neutral::Move,
+    error::{ClientErr, ConnectionClosedErr, HandlerErr, ReceiveErr, SendErr},
+    i_client_handler::{
+        cancel::{CancelReason, CancelToken},
+        handler_trait::{HandlerControl, IClientHandler},
+        middleware::Middleware,
+        move_context::{MoveContext, DEFAULT_MOVE_TIME_LIMIT},
+        state_tracker::StateTracker,
+    },
+};
+use crate::{
+    internal::{ComMessage, GameResult, GameState, RoomMessage},
+    neutral::{Move, Team},
+    season::{piranhas::Piranhas, GameType, Season},
+    socha_com::{ComHandler, ComSender, ReconnectPolicy},
+    transcript::TranscriptRecorder,
 };
 
 #[derive(Debug, Clone)]
 pub enum SendCommnad {
     Move(Move),
     SendRaw { xml: String },
+}
 
-    Admin(SendAdminCommand),
+/// true if the runtime can play `game_type`, the year is not checked
+pub fn supports_game_type(game_type: &GameType) -> bool {
+    game_type.name == Piranhas::game_type().name
 }
+
+/// lets a handler send commands to the server from any of its callbacks
+/// handed to the handler through `IClientHandler::on_connected`
 #[derive(Debug, Clone)]
-pub enum SendAdminCommand {
-    Authenticate {
-        pass: String,
-    },
-    Observe {
-        room_id: String,
-    },
-    Pause {
-        room_id: String,
-        pause: bool,
-    },
-    /// step a pause room forward by one move
-    Step {
-        room_id: String,
-    },
-    Cancel {
-        room_id: String,
-    },
-    /// prepares a new room
-    Prepare {
-        pause: bool,
-    },
+pub struct CommandSender {
+    outbox: Outbox,
+}
+
+impl CommandSender {
+    /// returns false if the connection is already gone or an interceptor dropped `cmd`
+    pub fn send(&self, cmd: SendCommnad) -> bool {
+        self.outbox.send(cmd).is_some()
+    }
+}
+
+/// the way to the writer thread, every command passes the outbound interceptors
+/// on the sending thread first
+#[derive(Debug, Clone)]
+struct Outbox {
+    tx: Sender<SendCommnad>,
+    middleware: Arc<Mutex<Middleware>>,
+}
+
+impl Outbox {
+    /// returns the command as it was queued, none if it was dropped or the writer is gone
+    fn send(&self, cmd: SendCommnad) -> Option<SendCommnad> {
+        let cmd = self.middleware.lock().unwrap().outbound(cmd)?;
+        self.tx.send(cmd.clone()).ok()?;
+        Some(cmd)
+    }
+
+    /// returns the move as it was queued, none if it was dropped or replaced by another command
+    fn send_move(&self, mv: Move) -> Option<Move> {
+        match self.send(SendCommnad::Move(mv))? {
+            SendCommnad::Move(mv) => Some(mv),
+            other => {
+                info!("move {:?} was replaced by {:?}", mv, other);
+                None
+            }
+        }
+    }
+}
+
+/// everything `start_iclient_with_config` needs to connect and run a handler
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub addr: String,
+    pub reservation_code: Option<String>,
+    /// only piranhas is played, see `supports_game_type`
+    pub game_type: GameType,
+    /// maximum time given to `while_waiting`
+    pub timeout: Duration,
+    /// the time limit per move assumed for `MoveContext::deadline`, counted from receiving the
+    /// move request. the server does not tell its limit, set this to match the server
+    pub move_time_limit: Duration,
+    /// if set, all traffic is recorded into a transcript file at this path
+    /// see `crate::transcript`
+    pub transcript_path: Option<PathBuf>,
+    /// used for the initial join and to rejoin with the reservation code,
+    /// if the connection drops before the game is over
+    pub reconnect_policy: ReconnectPolicy,
+    /// what happens after errors, before `IClientHandler::on_error` is called
+    pub recovery: RecoveryPolicy,
+}
+
+/// what the runtime does when `calculate_move` fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveRecovery {
+    /// send the best published move, or else a legal move from `possible_moves`
+    #[default]
+    Fallback,
+    /// send nothing and leave the game
+    Leave,
+}
+
+/// how the runtime recovers from each kind of `ClientErr`
+/// connection drops are handled by `ClientConfig::reconnect_policy`,
+/// a connection that can't be recovered always ends `start_iclient` with the error
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryPolicy {
+    /// `calculate_move` returned an error or panicked
+    pub move_failed: MoveRecovery,
+    /// a move or command could not be sent
+    pub send_failed: HandlerControl,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            addr: "localhost:13050".to_string(),
+            reservation_code: None,
+            game_type: Piranhas::game_type(),
+            timeout: Duration::from_secs(1),
+            move_time_limit: DEFAULT_MOVE_TIME_LIMIT,
+            transcript_path: None,
+            reconnect_policy: ReconnectPolicy::default(),
+            recovery: RecoveryPolicy::default(),
+        }
+    }
+}
+
+/// how long the server gets to end the protocol after the handler left
+pub(crate) const LEAVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// why `start_iclient` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientEnd {
+    /// the server ended the protocol or closed the connection after the game
+    #[default]
+    ServerEnded,
+    /// a callback returned `HandlerControl::Leave`
+    HandlerLeft,
+    /// a callback returned `HandlerControl::Stop`
+    HandlerStopped,
+}
+
+/// what `start_iclient` returns once the client is done
+#[derive(Debug, Clone, Default)]
+pub struct ClientSummary {
+    pub room_id: Option<String>,
+    pub team: Option<Team>,
+    /// none if the client stopped before the game was over
+    pub result: Option<GameResult>,
+    /// the state from the last memento
+    pub last_state: GameState,
+    pub moves_sent: u32,
+    /// mementos that did not match the locally computed state, see `StateTracker`
+    pub desyncs: u32,
+    pub end: ClientEnd,
 }
 
 pub fn start_iclient<I>(
     addr: &str,
     opt_reservation_code: Option<&str>,
+    game_type: &GameType,
     i_client_handler: &mut I,
-    thread_sleep_time: Duration,
     timeout: Duration,
-) -> Result<(), ReceiveErr>
+) -> Result<ClientSummary, ReceiveErr>
+where
+    I: IClientHandler,
+{
+    let config = ClientConfig {
+        addr: addr.to_string(),
+        reservation_code: opt_reservation_code.map(|s| s.to_string()),
+        game_type: game_type.clone(),
+        timeout,
+        ..Default::default()
+    };
+    start_iclient_with_config(&config, i_client_handler)
+}
+
+/// BLOCKING: connects, runs `i_client_handler` until the server ends the protocol
+/// or a callback returns `HandlerControl::Leave` or `HandlerControl::Stop`
+pub fn start_iclient_with_config<I>(
+    config: &ClientConfig,
+    i_client_handler: &mut I,
+) -> Result<ClientSummary, ReceiveErr>
+where
+    I: IClientHandler,
+{
+    start_iclient_with_middleware(config, i_client_handler, Middleware::new())
+}
+
+/// BLOCKING: like `start_iclient_with_config`, every message from the server and every
+/// command to it passes `middleware` first, see `crate::i_client_handler::middleware`
+pub fn start_iclient_with_middleware<I>(
+    config: &ClientConfig,
+    i_client_handler: &mut I,
+    middleware: Middleware,
+) -> Result<ClientSummary, ReceiveErr>
 where
     I: IClientHandler,
 {
-    use crate::socha_com::ComHandler;
+    run_client(config, i_client_handler, middleware, &Blocking)
+}
+
+/// BLOCKING: like `start_iclient_with_middleware`, in anytime mode: `calculate_move` runs on a
+/// worker thread and the framework sends `MoveContext::best` `margin` before the deadline,
+/// unless `calculate_move` returned already. with nothing published a legal move from
+/// `possible_moves` is sent instead.
+///
+/// WARNING: the worker borrows the handler, so no further message is dispatched before
+/// `calculate_move` returns. it has to return soon after `ctx.best.should_stop()` turns true,
+/// a search that ignores it blocks the client and loses the next move to the timeout
+pub fn start_iclient_anytime<I>(
+    config: &ClientConfig,
+    i_client_handler: &mut I,
+    margin: Duration,
+    middleware: Middleware,
+) -> Result<ClientSummary, ReceiveErr>
+where
+    I: IClientHandler + Send,
+{
+    run_client(config, i_client_handler, middleware, &Anytime { margin })
+}
 
-    let mut com = ComHandler::join(addr, opt_reservation_code)?;
-    let (msg_tx, msg_rx) = unbounded::<ComMessage>();
-    let (watch_tx, watch_rx) = unbounded::<ComMessage>();
+/// BLOCKING: the runtime behind all `start_iclient*` functions
+fn run_client<I>(
+    config: &ClientConfig,
+    i_client_handler: &mut I,
+    middleware: Middleware,
+    calculate: &dyn Calculate<I>,
+) -> Result<ClientSummary, ReceiveErr>
+where
+    I: IClientHandler,
+{
+    if !supports_game_type(&config.game_type) {
+        return Err(ReceiveErr::UnsupportedGameType(config.game_type.clone()));
+    }
+    let recorder = match &config.transcript_path {
+        Some(path) => Some(TranscriptRecorder::create(path)?),
+        None => None,
+    };
+    let reconnect_policy = config.reconnect_policy.clone();
+    let mut com = ComHandler::<Piranhas>::join_with_policy(
+        &config.addr,
+        config.reservation_code.as_deref(),
+        &config.game_type,
+        recorder,
+        &reconnect_policy,
+    )?;
+    let sender = com.sender();
+    // closes or shuts down the connection when the handler leaves or stops
+    let control_sender = com.sender();
+    let reader_sender = com.sender();
+    let (event_tx, event_rx) = unbounded::<ReaderEvent>();
     let (out_tx, out_rx) = unbounded::<SendCommnad>();
-    let reader_handle = std::thread::spawn(move || loop {
-        // messages from server
-        match com.try_for_com_message() {
-            Ok(Some(msg)) => {
-                let _ = msg_tx.send(msg.clone());
-                let _ = watch_tx.send(msg);
-            }
-            Ok(None) => {
-                std::thread::sleep(thread_sleep_time);
+    // never sent on, dropping it stops the writer
+    let (stop_tx, stop_rx) = bounded::<()>(0);
+    let (send_err_tx, send_err_rx) = unbounded::<SendErr>();
+    let room_id: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let cancel_slot = Arc::new(Mutex::new(CancelSlot::default()));
+    let outbox = Outbox {
+        tx: out_tx,
+        middleware: Arc::new(Mutex::new(middleware)),
+    };
+
+    // blocks on the socket, until the server sends something
+    let reader_cancel_slot = cancel_slot.clone();
+    let reader_middleware = outbox.middleware.clone();
+    let reader_handle = std::thread::spawn(move || {
+        let res = read_loop(
+            &mut com,
+            &reader_sender,
+            &reconnect_policy,
+            &event_tx,
+            &reader_cancel_slot,
+            &reader_middleware,
+        );
+        reader_cancel_slot
+            .lock()
+            .unwrap()
+            .cancelled_by(u64::MAX, CancelReason::Disconnected);
+        res
+    });
+
+    // blocks on the command channel, so commands are sent the moment they are issued
+    let writer_room_id = room_id.clone();
+    let writer_handle = std::thread::spawn(move || loop {
+        select! {
+            recv(out_rx) -> cmd => match cmd {
+                Ok(cmd) => {
+                    if let Err(e) = execute_command(&sender, &writer_room_id, cmd) {
+                        info!("sending command failed: {:?}", e);
+                        let _ = send_err_tx.send(e);
+                    }
+                }
+                Err(_) => break,
+            },
+            recv(stop_rx) -> _ => break,
+        }
+    });
+
+    let mut summary = ClientSummary::default();
+    let mut tracker = StateTracker::new();
+    // set while leaving, the connection is shut down if the server takes longer
+    let mut leave_deadline: Option<Instant> = None;
+    let mut control = i_client_handler.on_connected(CommandSender {
+        outbox: outbox.clone(),
+    });
+    // ends once the reader thread is done and all its events are handled
+    loop {
+        match control {
+            HandlerControl::Continue => {}
+            HandlerControl::Leave => {
+                if leave_deadline.is_none() {
+                    info!("handler is leaving");
+                    summary.end = ClientEnd::HandlerLeft;
+                    if let Err(e) = control_sender.send_close() {
+                        info!("sending close failed: {:?}", e);
+                    }
+                    leave_deadline = Some(Instant::now() + LEAVE_TIMEOUT);
+                }
             }
-            Err(_e) => {
+            HandlerControl::Stop => {
+                info!("handler stopped the client");
+                summary.end = ClientEnd::HandlerStopped;
+                control_sender.shutdown();
                 break;
             }
         }
-        // forwarding messages from the main loop to the server
-        if let Ok(out_msg) = out_rx.try_recv() {
-            match out_msg {
-                SendCommnad::Move(mv) => {
-                    let _ = com.send_move(mv.from.0, mv.from.1, mv.dir);
-                }
-                SendCommnad::SendRaw { xml } => {
-                    let _ = com.send_raw(&xml);
+        let leave_timeout = match leave_deadline {
+            Some(deadline) => after(deadline.saturating_duration_since(Instant::now())),
+            None => never(),
+        };
+        let event = select! {
+            recv(event_rx) -> event => match event {
+                Ok(event) => event,
+                Err(_) => break,
+            },
+            recv(send_err_rx) -> err => match err {
+                Ok(err) => {
+                    let err = ClientErr::Send(err);
+                    control = i_client_handler.on_error(&err).max(config.recovery.send_failed);
+                    continue;
                 }
-                SendCommnad::Admin(_admin_cmd) => {
-                    // todo: implement (shouldnt be necessary for the i_client_handler)
+                // the writer is gone, nothing can be sent anymore
+                Err(_) => break,
+            },
+            recv(leave_timeout) -> _ => {
+                info!("server did not end the protocol after close, shutting down");
+                control_sender.shutdown();
+                break;
+            },
+        };
+        control = match event {
+            ReaderEvent::Reconnected if leave_deadline.is_none() => {
+                // mementos might have been missed
+                tracker.reset();
+                i_client_handler.on_reconnected()
+            }
+            ReaderEvent::Reconnected => HandlerControl::Continue,
+            // after leaving only the result is of interest
+            ReaderEvent::Message {
+                msg: ComMessage::Room { msg, .. },
+                ..
+            } if leave_deadline.is_some() => {
+                if let RoomMessage::Result(result) = *msg {
+                    summary.result = Some(*result);
                 }
+                HandlerControl::Continue
             }
+            ReaderEvent::Message { .. } if leave_deadline.is_some() => HandlerControl::Continue,
+            ReaderEvent::Message {
+                msg,
+                received_at,
+                seq,
+            } => dispatch(
+                i_client_handler,
+                config,
+                &mut summary,
+                &mut tracker,
+                msg,
+                received_at,
+                &Dispatch {
+                    outbox: &outbox,
+                    cancel_slot: &cancel_slot,
+                    seq,
+                    room_id: &room_id,
+                    calculate,
+                },
+            ),
+        };
+    }
+    info!("dispatch finished, exiting");
+    drop(stop_tx);
+    let _ = writer_handle.join();
+    match reader_handle.join() {
+        // the reader sees the shut down socket as an error, which is expected after stopping
+        Ok(Err(_)) if summary.end == ClientEnd::HandlerStopped => Ok(summary),
+        Ok(Err(e)) => {
+            i_client_handler.on_error(&ClientErr::Receive(&e));
+            Err(e)
         }
-    });
+        Ok(Ok(())) => Ok(summary),
+        Err(payload) => {
+            let e = ReceiveErr::ReaderPanicked(panic_message(payload.as_ref()));
+            i_client_handler.on_error(&ClientErr::Receive(&e));
+            Err(e)
+        }
+    }
+}
 
-    loop {
-        use crossbeam_channel::TryRecvError;
+/// the channels `dispatch` needs
+struct Dispatch<'a, I> {
+    outbox: &'a Outbox,
+    cancel_slot: &'a Mutex<CancelSlot>,
+    /// sequence number of the dispatched message
+    seq: u64,
+    room_id: &'a Mutex<Option<String>>,
+    calculate: &'a dyn Calculate<I>,
+}
+
+/// the token of the current `while_waiting`, cancelled by the reader thread
+#[derive(Debug, Default)]
+struct CancelSlot {
+    token: CancelToken,
+    /// sequence number and reason of the last message that cancels
+    last_cancel: Option<(u64, CancelReason)>,
+}
 
-        use crate::internal::RoomMessage;
+impl CancelSlot {
+    /// the reader read message `seq`, which cancels for `reason`
+    fn cancelled_by(&mut self, seq: u64, reason: CancelReason) {
+        self.last_cancel = Some((seq, reason));
+        self.token.cancel(reason);
+    }
 
-        match msg_rx.try_recv() {
-            Ok(com_message) => match com_message {
-                ComMessage::Joined(joined) => {
-                    info!("joined room {}", joined.room_id);
-                    i_client_handler.on_game_joined(&joined.room_id);
+    /// a fresh token for the move request `seq`, cancelled right away
+    /// if the reader already read a cancelling message after it
+    fn install(&mut self, seq: u64) -> CancelToken {
+        self.token = CancelToken::new();
+        if let Some((cancel_seq, reason)) = self.last_cancel {
+            if cancel_seq > seq {
+                self.token.cancel(reason);
+            }
+        }
+        self.token.clone()
+    }
+}
+
+/// why `msg` cancels a waiting handler, if it does
+fn cancel_reason(msg: &ComMessage) -> Option<CancelReason> {
+    match msg {
+        ComMessage::Left(_) => Some(CancelReason::RoomLeft),
+        ComMessage::Room { msg, .. } => match **msg {
+            RoomMessage::MoveRequest => Some(CancelReason::MoveRequest),
+            RoomMessage::Result(_) => Some(CancelReason::GameOver),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// BLOCKING: reads until the server ends the protocol, hands every message to the dispatch loop
+/// and reconnects after connection drops
+fn read_loop(
+    com: &mut ComHandler,
+    reader_sender: &ComSender,
+    reconnect_policy: &ReconnectPolicy,
+    event_tx: &Sender<ReaderEvent>,
+    cancel_slot: &Mutex<CancelSlot>,
+    middleware: &Mutex<Middleware>,
+) -> Result<(), ReceiveErr> {
+    // set once the game is over, a closed connection is expected from then on
+    let mut game_over = false;
+    let mut seq = 0;
+    loop {
+        match com.recv_com_message() {
+            Ok(msg) => {
+                let received_at = Instant::now();
+                let Some(msg) = middleware.lock().unwrap().inbound(msg, received_at) else {
+                    continue;
+                };
+                seq += 1;
+                if let Some(reason) = cancel_reason(&msg) {
+                    if reason != CancelReason::MoveRequest {
+                        game_over = true;
+                    }
+                    cancel_slot.lock().unwrap().cancelled_by(seq, reason);
                 }
-                ComMessage::Left(left) => {
-                    info!("left room {}", left.room_id);
-                    i_client_handler.on_game_left();
+                let _ = event_tx.send(ReaderEvent::Message {
+                    msg,
+                    received_at,
+                    seq,
+                });
+            }
+            // closed on purpose by leaving or stopping
+            Err(_) if reader_sender.is_closed() => return Ok(()),
+            Err(e) if game_over || !is_connection_drop(&e) => {
+                return match e {
+                    ReceiveErr::ConnectionClosed(_) if game_over => Ok(()),
+                    ReceiveErr::ConnectionClosed(ConnectionClosedErr::ProtocolEnded) => Ok(()),
+                    e => Err(e),
+                };
+            }
+            Err(e) => {
+                if reconnect_policy.max_attempts == 0 {
+                    return Err(e);
                 }
-                ComMessage::Room(room_msg) => match *room_msg {
-                    RoomMessage::Memento(state) => {
-                        info!("got board: \n{}", state.board);
-                        info!("turn {}, class {:?}", state.turn, state.class);
-                        i_client_handler.on_gamestate_update(*state);
+                info!("connection dropped, reconnecting: {:?}", e);
+                com.reconnect(reconnect_policy)?;
+                info!("rejoined the room");
+                let _ = event_tx.send(ReaderEvent::Reconnected);
+            }
+        }
+    }
+}
+
+/// hands one message to the matching callback and keeps `summary` up to date
+fn dispatch<I>(
+    i_client_handler: &mut I,
+    config: &ClientConfig,
+    summary: &mut ClientSummary,
+    tracker: &mut StateTracker,
+    com_message: ComMessage,
+    received_at: Instant,
+    channels: &Dispatch<I>,
+) -> HandlerControl
+where
+    I: IClientHandler,
+{
+    match com_message {
+        ComMessage::Joined(joined) => {
+            info!("joined room {}", joined.room_id);
+            *channels.room_id.lock().unwrap() = Some(joined.room_id.clone());
+            summary.room_id = Some(joined.room_id.clone());
+            i_client_handler.on_game_joined(&joined.room_id)
+        }
+        ComMessage::Left(left) => {
+            info!("left room {}", left.room_id);
+            i_client_handler.on_game_left()
+        }
+        ComMessage::Room { msg, .. } => match *msg {
+            RoomMessage::Memento(state) => {
+                info!("got board: \n{}", state.board);
+                info!("turn {}, class {:?}", state.turn, state.class);
+                summary.last_state = *state;
+                let mut control = HandlerControl::Continue;
+                if let Some(transition) = tracker.update(&summary.last_state) {
+                    if let Some(desync) = &transition.desync {
+                        info!("{}", desync);
+                        summary.desyncs += 1;
+                        control = i_client_handler.on_desync(desync);
                     }
-                    RoomMessage::WelcomeMessage => {
-                        info!("got welcome message");
-                        i_client_handler.on_welcome_message();
+                    if transition.by_opponent {
+                        control = control.max(i_client_handler.on_opponent_move(
+                            transition.mv,
+                            &transition.before,
+                            &summary.last_state,
+                        ));
                     }
-                    RoomMessage::MoveRequest => {
-                        info!("got move request");
-
-                        let mv = i_client_handler.calculate_move();
-                        out_tx.send(SendCommnad::Move(mv)).unwrap();
-                        let cancel_handler = ComCancelHandler::new_from_receiver(
-                            watch_rx.clone(),
-                            timeout,
-                            thread_sleep_time,
-                        );
-                        i_client_handler.while_waiting(cancel_handler);
+                }
+                control.max(i_client_handler.on_gamestate_update(summary.last_state.clone()))
+            }
+            RoomMessage::WelcomeMessage(welcome) => {
+                info!("got welcome message, playing as team {:?}", welcome.team);
+                if let Some(team) = welcome.team {
+                    summary.team = Some(team);
+                    tracker.set_team(team);
+                }
+                i_client_handler.on_welcome_message(&welcome)
+            }
+            RoomMessage::MoveRequest => {
+                info!("got move request");
+                // installed before the move is sent, so no reply of the server is missed
+                let cancel = channels.cancel_slot.lock().unwrap().install(channels.seq);
+
+                let ctx = MoveContext::new(
+                    received_at,
+                    config.move_time_limit,
+                    summary.team,
+                    summary.last_state.clone(),
+                );
+                let recovery = config.recovery.move_failed;
+                let (sent, err) =
+                    channels
+                        .calculate
+                        .calculate(i_client_handler, &ctx, recovery, channels.outbox);
+                if let Some(mv) = sent {
+                    summary.moves_sent += 1;
+                    tracker.sent_move(mv);
+                }
+                if let Some(err) = err {
+                    let control = i_client_handler.on_error(&err);
+                    if sent.is_none() {
+                        return control.max(HandlerControl::Leave);
                     }
-                    RoomMessage::Result(result) => {
-                        info!("got result: \n{:#?}", result);
-                        i_client_handler.on_game_result(&result);
+                    if control != HandlerControl::Continue {
+                        return control;
                     }
-                },
-                ComMessage::Admin(_) => {}
-            },
-            Err(TryRecvError::Empty) => {
-                std::thread::sleep(thread_sleep_time);
+                }
+                // without a move sent it is still our turn, there is nothing to ponder
+                let Some(mv) = sent else {
+                    info!("no move was sent, skipping while_waiting");
+                    return HandlerControl::Continue;
+                };
+                let mut after_our_move = summary.last_state.clone();
+                after_our_move.perform_move(mv);
+                cancel.cancel_after(config.timeout);
+                i_client_handler.while_waiting(&after_our_move, cancel);
+                HandlerControl::Continue
             }
-            Err(TryRecvError::Disconnected) => {
-                info!("worker channel disconnected, exiting");
-                break;
+            RoomMessage::Result(result) => {
+                info!("got result: \n{:#?}", result);
+                let control = i_client_handler.on_game_result(&result);
+                summary.result = Some(*result);
+                control
             }
+        },
+        // answers to admin commands, a player does not send any
+        ComMessage::Admin(msg) => {
+            info!("ignoring admin message {:?}", msg);
+            HandlerControl::Continue
+        }
+        ComMessage::Error(err) => {
+            info!("got server error: {}", err);
+            i_client_handler.on_server_error(&err)
         }
     }
-    let _ = reader_handle.join();
-    Ok(())
 }
 
-use crossbeam_channel::{unbounded, Receiver};
+/// result of `calculate_move`, with panics caught
+type Calculated = std::thread::Result<Result<Move, HandlerErr>>;
 
-pub struct ComCancelHandler {
-    flag: Arc<AtomicBool>,
-    watchdog_handle: Option<JoinHandle<()>>,
+/// the move to send for `calculated` and the error to report, if any
+/// failures are recovered from as `recovery` says
+fn settle_move(
+    calculated: Calculated,
+    ctx: &MoveContext,
+    recovery: MoveRecovery,
+) -> (Option<Move>, Option<ClientErr<'static>>) {
+    let err = match calculated {
+        Ok(Ok(mv)) => return (Some(mv), None),
+        Ok(Err(e)) => ClientErr::CalculateMove(e),
+        Err(panic) => ClientErr::CalculateMovePanicked(panic_message(panic.as_ref())),
+    };
+    info!("calculate_move failed: {:?}", err);
+    let mv = match recovery {
+        MoveRecovery::Fallback => ctx.best_or_fallback(),
+        MoveRecovery::Leave => None,
+    };
+    (mv, Some(err))
 }
 
-impl ComCancelHandler {
-    pub fn new_from_receiver(
-        rx: Receiver<ComMessage>,
-        timeout: Duration,
-        thread_sleep_time: Duration,
-    ) -> Self {
-        let flag = Arc::new(AtomicBool::new(false));
-        let flag_clone = flag.clone();
-
-        let handle = std::thread::spawn(move || {
-            // drain messages
-            while let Ok(_m) = rx.try_recv() {}
-
-            let start = Instant::now();
-            loop {
-                if flag_clone.load(Ordering::SeqCst) {
-                    break;
-                }
-                match rx.try_recv() {
-                    Ok(msg) => {
-                        match msg {
-                            ComMessage::Room(room_msg) => {
-                                if *room_msg == RoomMessage::MoveRequest {
-                                    // next move request arrived, cancel
-                                    flag_clone.store(true, Ordering::SeqCst);
-                                    break;
-                                }
-                            }
-                            ComMessage::Left(_id) => {
-                                // left the room, cancel
-                                flag_clone.store(true, Ordering::SeqCst);
-                                break;
-                            }
-                            _ => {}
-                        }
-                    }
-                    Err(crossbeam_channel::TryRecvError::Empty) => {}
-                    Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                        flag_clone.store(true, Ordering::SeqCst);
-                        break;
-                    }
-                }
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
 
-                if start.elapsed() >= timeout {
-                    flag_clone.store(true, Ordering::SeqCst);
-                    break;
-                }
+/// how `dispatch` runs `calculate_move` and sends its move
+trait Calculate<I> {
+    /// returns the sent move and the error of `calculate_move`, if any
+    fn calculate(
+        &self,
+        i_client_handler: &mut I,
+        ctx: &MoveContext,
+        recovery: MoveRecovery,
+        outbox: &Outbox,
+    ) -> (Option<Move>, Option<ClientErr<'static>>);
+}
 
-                thread::sleep(thread_sleep_time);
-            }
-        });
+/// `calculate_move` runs on the dispatch thread, its move is sent once it returned
+struct Blocking;
 
-        ComCancelHandler {
-            flag,
-            watchdog_handle: Some(handle),
+impl<I: IClientHandler> Calculate<I> for Blocking {
+    fn calculate(
+        &self,
+        i_client_handler: &mut I,
+        ctx: &MoveContext,
+        recovery: MoveRecovery,
+        outbox: &Outbox,
+    ) -> (Option<Move>, Option<ClientErr<'static>>) {
+        let calculated = catch_unwind(AssertUnwindSafe(|| i_client_handler.calculate_move(ctx)));
+        if ctx.is_past_deadline() {
+            info!("move calculated {:?} after the deadline", ctx.elapsed());
         }
+        let (mv, err) = settle_move(calculated, ctx, recovery);
+        (mv.and_then(|mv| outbox.send_move(mv)), err)
     }
+}
+
+/// the anytime mode of `start_iclient_anytime`
+struct Anytime {
+    margin: Duration,
+}
 
-    pub fn is_cancelled(&self) -> bool {
-        self.flag.load(Ordering::SeqCst)
+impl<I: IClientHandler + Send> Calculate<I> for Anytime {
+    fn calculate(
+        &self,
+        i_client_handler: &mut I,
+        ctx: &MoveContext,
+        recovery: MoveRecovery,
+        outbox: &Outbox,
+    ) -> (Option<Move>, Option<ClientErr<'static>>) {
+        calculate_move_anytime(i_client_handler, ctx, self.margin, recovery, outbox)
     }
 }
 
-impl Drop for ComCancelHandler {
-    fn drop(&mut self) {
-        if let Some(h) = self.watchdog_handle.take() {
-            let _ = h.join();
+/// runs `calculate_move` on a worker thread and sends its move, or the best published move
+/// once `margin` before the deadline is reached. returns after the worker returned
+/// returns the sent move and the error of `calculate_move`, if any
+fn calculate_move_anytime<I>(
+    i_client_handler: &mut I,
+    ctx: &MoveContext,
+    margin: Duration,
+    recovery: MoveRecovery,
+    outbox: &Outbox,
+) -> (Option<Move>, Option<ClientErr<'static>>)
+where
+    I: IClientHandler + Send,
+{
+    let send_at = ctx.soft_deadline(margin);
+    let (done_tx, done_rx) = bounded::<Calculated>(1);
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            let calculated =
+                catch_unwind(AssertUnwindSafe(|| i_client_handler.calculate_move(ctx)));
+            let _ = done_tx.send(calculated);
+        });
+        let returned = select! {
+            recv(done_rx) -> calculated => calculated.ok(),
+            default(send_at.saturating_duration_since(Instant::now())) => None,
+        };
+        ctx.best.stop();
+        let (mv, mut err) = match returned {
+            Some(calculated) => settle_move(calculated, ctx, recovery),
+            None => {
+                info!(
+                    "calculate_move did not return {:?} after the request, sending the best move so far",
+                    ctx.elapsed()
+                );
+                (ctx.best_or_fallback(), None)
+            }
+        };
+        let mv = mv.and_then(|mv| outbox.send_move(mv));
+        if mv.is_none() {
+            info!("no move sent");
+        }
+        if err.is_none() {
+            // the worker is joined when the scope ends anyway, a late failure is still reported
+            let late = done_rx.recv_timeout(margin).or_else(|_| {
+                info!(
+                    "calculate_move still runs {:?} after the request, waiting for it to return",
+                    ctx.elapsed()
+                );
+                done_rx.recv()
+            });
+            err = match late {
+                Ok(Ok(Err(e))) => Some(ClientErr::CalculateMove(e)),
+                Ok(Err(panic)) => Some(ClientErr::CalculateMovePanicked(panic_message(
+                    panic.as_ref(),
+                ))),
+                _ => None,
+            };
         }
+        (mv, err)
+    })
+}
+
+/// what the reader thread hands to the dispatch loop
+enum ReaderEvent {
+    Message {
+        msg: ComMessage,
+        /// when it was read from the socket
+        received_at: Instant,
+        /// counts the messages read, starting at 1
+        seq: u64,
+    },
+    /// the connection dropped and the room was rejoined
+    Reconnected,
+}
+
+/// sends `cmd` to the server
+fn execute_command(
+    sender: &ComSender,
+    room_id: &Mutex<Option<String>>,
+    cmd: SendCommnad,
+) -> Result<(), SendErr> {
+    match cmd {
+        SendCommnad::Move(mv) => match room_id.lock().unwrap().as_deref() {
+            Some(room_id) => sender.send_move(room_id, &mv),
+            None => Err(SendErr::NoRoomId),
+        },
+        SendCommnad::SendRaw { xml } => sender.send_raw(&xml),
     }
 }
+
+/// errors after which reconnecting might help
+fn is_connection_drop(err: &ReceiveErr) -> bool {
+    matches!(
+        err,
+        ReceiveErr::Io(_) | ReceiveErr::ConnectionClosed(ConnectionClosedErr::ServerClosed)
+    )
+}
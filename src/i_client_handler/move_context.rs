@@ -0,0 +1,123 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    internal::GameState,
+    neutral::{Move, Team},
+};
+
+/// the usual time limit of the contest server for a move. the server does not send its limit,
+/// this is a hard-coded assumption
+pub const DEFAULT_MOVE_TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// how long before the deadline the anytime mode sends the best move, if it is not done by then
+pub const DEFAULT_ANYTIME_MARGIN: Duration = Duration::from_millis(200);
+
+/// the best move found so far, shared between the search and the framework
+/// in anytime mode (`crate::i_client_handler::start_iclient_anytime`) the framework sends the latest published move,
+/// if `calculate_move` did not return in time
+#[derive(Debug, Clone, Default)]
+pub struct BestMove {
+    inner: Arc<BestMoveInner>,
+}
+
+#[derive(Debug, Default)]
+struct BestMoveInner {
+    mv: Mutex<Option<Move>>,
+    stop: AtomicBool,
+}
+
+impl BestMove {
+    /// replaces the best move, call it whenever the search found a better one
+    pub fn publish(&self, mv: Move) {
+        *self.inner.mv.lock().unwrap() = Some(mv);
+    }
+
+    pub fn get(&self) -> Option<Move> {
+        *self.inner.mv.lock().unwrap()
+    }
+
+    /// true once the framework sent a move, the search should return as soon as possible
+    pub fn should_stop(&self) -> bool {
+        self.inner.stop.load(Ordering::SeqCst)
+    }
+
+    pub fn stop(&self) {
+        self.inner.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// everything known about a move request, handed to `IClientHandler::calculate_move`
+#[derive(Debug, Clone)]
+pub struct MoveContext {
+    /// when the move request was read from the socket
+    pub received_at: Instant,
+    /// `received_at` plus `ClientConfig::move_time_limit` (2s by default). it is not a value
+    /// from the server: the server counts from sending the request, so network latency is
+    /// not included, and a server with a different limit is not noticed
+    pub deadline: Instant,
+    /// the team we play as, none if the welcome message was not received yet
+    pub team: Option<Team>,
+    /// the state from the last memento
+    pub state: GameState,
+    /// publish improving moves here, used by the anytime mode
+    pub best: BestMove,
+}
+
+impl MoveContext {
+    pub fn new(
+        received_at: Instant,
+        time_limit: Duration,
+        team: Option<Team>,
+        state: GameState,
+    ) -> Self {
+        MoveContext {
+            received_at,
+            deadline: received_at + time_limit,
+            team,
+            state,
+            best: BestMove::default(),
+        }
+    }
+
+    /// time since the move request was received
+    pub fn elapsed(&self) -> Duration {
+        self.received_at.elapsed()
+    }
+
+    /// time until the deadline, zero once it passed
+    pub fn time_left(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// the deadline minus `margin`, which should cover sending the move and network latency
+    pub fn soft_deadline(&self, margin: Duration) -> Instant {
+        self.deadline
+            .checked_sub(margin)
+            .unwrap_or(self.received_at)
+            .max(self.received_at)
+    }
+
+    /// time left until `soft_deadline(margin)`
+    pub fn budget(&self, margin: Duration) -> Duration {
+        self.soft_deadline(margin)
+            .saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_past_deadline(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// the published best move, or else the first legal move
+    /// none only if there is no legal move at all
+    pub fn best_or_fallback(&self) -> Option<Move> {
+        self.best
+            .get()
+            .or_else(|| self.state.possible_moves().first().copied())
+    }
+}
@@ -0,0 +1,115 @@
+//! pondering: searching while the opponent thinks
+//!
+//! the crate has no search of its own, `Ponderer` runs the bot's search on the position
+//! after every opponent reply and keeps one result per reply. once the opponent's move is known
+//! (`IClientHandler::on_opponent_move`), `Ponderer::take` hands out the result for that reply,
+//! e.g. a subtree or transposition entries to continue from
+
+use crate::{internal::GameState, neutral::Move};
+
+/// the search result for one opponent reply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pondered<T> {
+    pub reply: Move,
+    /// the deepest depth that was searched to the end
+    pub depth: u32,
+    pub result: T,
+}
+
+/// runs a search on every opponent reply, deepening pass by pass
+#[derive(Debug, Clone)]
+pub struct Ponderer<T> {
+    /// the position after our move
+    state: Option<GameState>,
+    results: Vec<Pondered<T>>,
+    /// no pass searches deeper than this
+    pub max_depth: u32,
+}
+
+impl<T> Default for Ponderer<T> {
+    fn default() -> Self {
+        Ponderer {
+            state: None,
+            results: Vec::new(),
+            max_depth: u32::MAX,
+        }
+    }
+}
+
+impl<T> Ponderer<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// BLOCKING: searches the position after every opponent reply of `state`, first with depth 1
+    /// for all replies, then depth 2, and so on. returns once `should_stop` returns true,
+    /// `max_depth` is done or there are no replies
+    /// `search(position, depth, should_stop)` is the bot's search; results of a search that was
+    /// stopped before it finished are dropped, the previous depth is kept
+    /// pondering a different `state` than last time forgets the old results
+    pub fn ponder<S, F>(&mut self, state: &GameState, should_stop: S, mut search: F)
+    where
+        S: Fn() -> bool,
+        F: FnMut(&GameState, u32, &dyn Fn() -> bool) -> T,
+    {
+        if self.state.as_ref() != Some(state) {
+            self.state = Some(state.clone());
+            self.results.clear();
+        }
+        let replies = state.possible_moves();
+        if replies.is_empty() {
+            return;
+        }
+        let mut depth = 1;
+        while depth <= self.max_depth {
+            for reply in &replies {
+                if should_stop() {
+                    return;
+                }
+                if self.get(*reply).is_some_and(|p| p.depth >= depth) {
+                    continue;
+                }
+                let mut position = state.clone();
+                position.perform_move(*reply);
+                let result = search(&position, depth, &should_stop);
+                if should_stop() {
+                    return;
+                }
+                let pondered = Pondered {
+                    reply: *reply,
+                    depth,
+                    result,
+                };
+                match self.results.iter_mut().find(|p| p.reply == *reply) {
+                    Some(old) => *old = pondered,
+                    None => self.results.push(pondered),
+                }
+            }
+            depth += 1;
+        }
+    }
+
+    /// the result for `reply`, if it was searched
+    pub fn get(&self, reply: Move) -> Option<&Pondered<T>> {
+        self.results.iter().find(|p| p.reply == reply)
+    }
+
+    /// every result so far
+    pub fn results(&self) -> &[Pondered<T>] {
+        &self.results
+    }
+
+    /// takes the result for the reply the opponent played and forgets everything else
+    pub fn take(&mut self, played: Move) -> Option<Pondered<T>> {
+        self.state = None;
+        let index = self.results.iter().position(|p| p.reply == played)?;
+        let pondered = self.results.swap_remove(index);
+        self.results.clear();
+        Some(pondered)
+    }
+
+    pub fn clear(&mut self) {
+        self.state = None;
+        self.results.clear();
+    }
+}
@@ -0,0 +1,420 @@
+//! scripted server: unit tests for `IClientHandler` implementations without sockets
+//!
+//! `run_script` feeds a fixed sequence of `ComMessage`s through the same `dispatch` as
+//! `start_iclient` and records which callbacks fired, what `calculate_move` returned and how
+//! long it took. every `while_waiting` gets a token that is already cancelled with the reason
+//! of the next cancelling message in the script that survives the inbound middleware, the reader
+//! thread would have read it by then
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::unbounded;
+
+use crate::{
+    error::{ClientErr, HandlerErr},
+    i_client_handler::{
+        cancel::{CancelReason, CancelToken},
+        cancel_reason, dispatch,
+        handler_trait::{HandlerControl, IClientHandler},
+        middleware::Middleware,
+        move_context::MoveContext,
+        state_tracker::{Desync, StateTracker},
+        Blocking, CancelSlot, ClientConfig, ClientEnd, ClientSummary, CommandSender, Dispatch,
+        Outbox, SendCommnad,
+    },
+    internal::{
+        ComMessage, GameResult, GameState, Joined, Left, RoomMessage, ServerError, WelcomeMessage,
+    },
+    neutral::{Move, Team},
+};
+
+/// the messages a scripted server sends, in order
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    /// used for all room messages added by the helpers
+    pub room_id: String,
+    pub msgs: Vec<ComMessage>,
+}
+
+impl Script {
+    pub fn new(room_id: &str) -> Self {
+        Script {
+            room_id: room_id.to_string(),
+            msgs: Vec::new(),
+        }
+    }
+
+    /// appends any message
+    pub fn then(mut self, msg: ComMessage) -> Self {
+        self.msgs.push(msg);
+        self
+    }
+
+    fn room(self, msg: RoomMessage) -> Self {
+        let room_id = self.room_id.clone();
+        self.then(ComMessage::Room {
+            room_id,
+            msg: Box::new(msg),
+        })
+    }
+
+    pub fn joined(self) -> Self {
+        let room_id = self.room_id.clone();
+        self.then(ComMessage::Joined(Joined { room_id }))
+    }
+
+    pub fn welcome(self, team: Team) -> Self {
+        self.room(RoomMessage::WelcomeMessage(WelcomeMessage {
+            team: Some(team),
+        }))
+    }
+
+    pub fn memento(self, state: GameState) -> Self {
+        self.room(RoomMessage::Memento(Box::new(state)))
+    }
+
+    pub fn move_request(self) -> Self {
+        self.room(RoomMessage::MoveRequest)
+    }
+
+    pub fn result(self, result: GameResult) -> Self {
+        self.room(RoomMessage::Result(Box::new(result)))
+    }
+
+    pub fn left(self) -> Self {
+        let room_id = self.room_id.clone();
+        self.then(ComMessage::Left(Left { room_id }))
+    }
+
+    pub fn error(self, err: ServerError) -> Self {
+        self.then(ComMessage::Error(err))
+    }
+}
+
+/// a callback of `IClientHandler` that fired, with the parts of its arguments worth comparing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Callback {
+    Connected,
+    GameJoined(String),
+    GameLeft,
+    WelcomeMessage(Option<Team>),
+    GamestateUpdate {
+        turn: u32,
+    },
+    Desync {
+        turn: u32,
+    },
+    OpponentMove(Move),
+    CalculateMove {
+        turn: u32,
+    },
+    GameResult,
+    ServerError(ServerError),
+    /// the error, formatted with `{:?}`
+    Error(String),
+    Reconnected,
+    /// `cancel` is the reason the token was already cancelled for, when the callback started
+    WhileWaiting {
+        turn: u32,
+        cancel: Option<CancelReason>,
+    },
+}
+
+/// one call of `calculate_move`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedMove {
+    pub turn: u32,
+    /// the returned move, or the error formatted with `{}`
+    pub result: Result<Move, String>,
+    pub took: Duration,
+}
+
+/// what `run_script` saw
+#[derive(Debug, Clone, Default)]
+pub struct ScriptReport {
+    /// as `start_iclient` would have returned it
+    pub summary: ClientSummary,
+    /// in the order they fired
+    pub callbacks: Vec<Callback>,
+    /// every call of `calculate_move`, a panicking call is not recorded
+    pub moves: Vec<RecordedMove>,
+    /// everything sent to the server, moves as well as commands from `CommandSender`
+    pub sent: Vec<SendCommnad>,
+}
+
+impl ScriptReport {
+    /// the moves sent to the server, including fallback moves
+    pub fn sent_moves(&self) -> Vec<Move> {
+        self.sent
+            .iter()
+            .filter_map(|cmd| match cmd {
+                SendCommnad::Move(mv) => Some(*mv),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn fired(&self, callback: &Callback) -> bool {
+        self.callbacks.contains(callback)
+    }
+}
+
+/// BLOCKING: dispatches `script` to `i_client_handler` like `start_iclient_with_config` with
+/// `config` would, without connecting. `addr`, `reservation_code`, `transcript_path` and
+/// `reconnect_policy` of `config` are not used, `calculate_move` runs on the calling thread
+/// ends after the last message or once a callback returns `HandlerControl::Stop`.
+/// after `HandlerControl::Leave` only results are taken into the summary
+pub fn run_script<I>(
+    config: &ClientConfig,
+    script: Script,
+    i_client_handler: &mut I,
+) -> ScriptReport
+where
+    I: IClientHandler,
+{
+    run_script_with_middleware(config, script, i_client_handler, Middleware::new())
+}
+
+/// BLOCKING: like `run_script`, every scripted message and every command passes `middleware`
+/// first. `ScriptReport::sent` holds the commands as they left the middleware.
+/// cancel reasons for `while_waiting` are taken from the messages the middleware kept, like the
+/// reader thread does. a message passes the middleware when it is dispatched, or earlier when
+/// `while_waiting` needs the next cancelling message after the sent move
+pub fn run_script_with_middleware<I>(
+    config: &ClientConfig,
+    script: Script,
+    i_client_handler: &mut I,
+    middleware: Middleware,
+) -> ScriptReport
+where
+    I: IClientHandler,
+{
+    let (out_tx, out_rx) = unbounded::<SendCommnad>();
+    let outbox = Outbox {
+        tx: out_tx,
+        middleware: Arc::new(Mutex::new(middleware)),
+    };
+    let cancel_slot = Mutex::new(CancelSlot::default());
+    let room_id = Mutex::new(None);
+    let mut recorder = Recorder {
+        inner: i_client_handler,
+        reader: ScriptReader {
+            msgs: script.msgs.into_iter(),
+            ahead: VecDeque::new(),
+            seq: 0,
+            middleware: &outbox.middleware,
+            cancel_slot: &cancel_slot,
+        },
+        callbacks: Vec::new(),
+        moves: Vec::new(),
+    };
+    let mut summary = ClientSummary::default();
+    let mut tracker = StateTracker::new();
+    let mut control = recorder.on_connected(CommandSender {
+        outbox: outbox.clone(),
+    });
+    loop {
+        apply_control(control, &mut summary);
+        if summary.end == ClientEnd::HandlerStopped {
+            break;
+        }
+        let Some((msg, received_at, seq)) = recorder.reader.next() else {
+            break;
+        };
+        if summary.end == ClientEnd::HandlerLeft {
+            if let ComMessage::Room { msg, .. } = msg {
+                if let RoomMessage::Result(result) = *msg {
+                    summary.result = Some(*result);
+                }
+            }
+            control = HandlerControl::Continue;
+            continue;
+        }
+        control = dispatch(
+            &mut recorder,
+            config,
+            &mut summary,
+            &mut tracker,
+            msg,
+            received_at,
+            &Dispatch {
+                outbox: &outbox,
+                cancel_slot: &cancel_slot,
+                seq,
+                room_id: &room_id,
+                calculate: &Blocking,
+            },
+        );
+    }
+    apply_control(control, &mut summary);
+    ScriptReport {
+        summary,
+        callbacks: recorder.callbacks,
+        moves: recorder.moves,
+        sent: out_rx.try_iter().collect(),
+    }
+}
+
+/// reads the script like the reader thread reads the socket: every message passes the
+/// inbound middleware once, only the ones it keeps get a sequence number and cancel the
+/// waiting handler
+struct ScriptReader<'a> {
+    msgs: std::vec::IntoIter<ComMessage>,
+    /// read, not dispatched yet
+    ahead: VecDeque<(ComMessage, Instant, u64)>,
+    seq: u64,
+    middleware: &'a Mutex<Middleware>,
+    cancel_slot: &'a Mutex<CancelSlot>,
+}
+
+impl ScriptReader<'_> {
+    /// reads the next message the middleware keeps into `ahead`, false at the end of the script
+    fn read(&mut self) -> bool {
+        for msg in self.msgs.by_ref() {
+            let received_at = Instant::now();
+            let Some(msg) = self.middleware.lock().unwrap().inbound(msg, received_at) else {
+                continue;
+            };
+            // sequence numbers start at 1, like the reader's
+            self.seq += 1;
+            if let Some(reason) = cancel_reason(&msg) {
+                self.cancel_slot
+                    .lock()
+                    .unwrap()
+                    .cancelled_by(self.seq, reason);
+            }
+            self.ahead.push_back((msg, received_at, self.seq));
+            return true;
+        }
+        self.cancel_slot
+            .lock()
+            .unwrap()
+            .cancelled_by(u64::MAX, CancelReason::Disconnected);
+        false
+    }
+
+    fn next(&mut self) -> Option<(ComMessage, Instant, u64)> {
+        if self.ahead.is_empty() {
+            self.read();
+        }
+        self.ahead.pop_front()
+    }
+
+    /// reads on until a message cancels `cancel`, the reader would have read it while the
+    /// handler waited. the server answers the sent move, so nothing is read before it left
+    fn read_until_cancelled(&mut self, cancel: &CancelToken) {
+        while !cancel.is_cancelled() && self.read() {}
+    }
+}
+
+/// the end `control` leads to, the first leave or stop is kept
+fn apply_control(control: HandlerControl, summary: &mut ClientSummary) {
+    if summary.end != ClientEnd::ServerEnded {
+        return;
+    }
+    summary.end = match control {
+        HandlerControl::Continue => ClientEnd::ServerEnded,
+        HandlerControl::Leave => ClientEnd::HandlerLeft,
+        HandlerControl::Stop => ClientEnd::HandlerStopped,
+    };
+}
+
+/// forwards every callback to `inner` and records it
+struct Recorder<'a, I> {
+    inner: &'a mut I,
+    reader: ScriptReader<'a>,
+    callbacks: Vec<Callback>,
+    moves: Vec<RecordedMove>,
+}
+
+impl<I: IClientHandler> IClientHandler for Recorder<'_, I> {
+    fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+        let turn = ctx.state.turn;
+        self.callbacks.push(Callback::CalculateMove { turn });
+        let start_t = Instant::now();
+        let res = self.inner.calculate_move(ctx);
+        self.moves.push(RecordedMove {
+            turn,
+            result: res.as_ref().map(|mv| *mv).map_err(|e| e.to_string()),
+            took: start_t.elapsed(),
+        });
+        res
+    }
+
+    fn on_gamestate_update(&mut self, state: GameState) -> HandlerControl {
+        self.callbacks
+            .push(Callback::GamestateUpdate { turn: state.turn });
+        self.inner.on_gamestate_update(state)
+    }
+
+    fn on_desync(&mut self, desync: &Desync) -> HandlerControl {
+        self.callbacks.push(Callback::Desync {
+            turn: desync.received.turn,
+        });
+        self.inner.on_desync(desync)
+    }
+
+    fn on_opponent_move(
+        &mut self,
+        mv: Move,
+        before: &GameState,
+        after: &GameState,
+    ) -> HandlerControl {
+        self.callbacks.push(Callback::OpponentMove(mv));
+        self.inner.on_opponent_move(mv, before, after)
+    }
+
+    fn on_connected(&mut self, commands: CommandSender) -> HandlerControl {
+        self.callbacks.push(Callback::Connected);
+        self.inner.on_connected(commands)
+    }
+
+    fn on_game_joined(&mut self, room_id: &str) -> HandlerControl {
+        self.callbacks
+            .push(Callback::GameJoined(room_id.to_string()));
+        self.inner.on_game_joined(room_id)
+    }
+
+    fn on_game_left(&mut self) -> HandlerControl {
+        self.callbacks.push(Callback::GameLeft);
+        self.inner.on_game_left()
+    }
+
+    fn on_game_result(&mut self, res: &GameResult) -> HandlerControl {
+        self.callbacks.push(Callback::GameResult);
+        self.inner.on_game_result(res)
+    }
+
+    fn on_welcome_message(&mut self, welcome: &WelcomeMessage) -> HandlerControl {
+        self.callbacks.push(Callback::WelcomeMessage(welcome.team));
+        self.inner.on_welcome_message(welcome)
+    }
+
+    fn on_server_error(&mut self, err: &ServerError) -> HandlerControl {
+        self.callbacks.push(Callback::ServerError(err.clone()));
+        self.inner.on_server_error(err)
+    }
+
+    fn on_error(&mut self, err: &ClientErr) -> HandlerControl {
+        self.callbacks.push(Callback::Error(format!("{:?}", err)));
+        self.inner.on_error(err)
+    }
+
+    fn on_reconnected(&mut self) -> HandlerControl {
+        self.callbacks.push(Callback::Reconnected);
+        self.inner.on_reconnected()
+    }
+
+    fn while_waiting(&mut self, state: &GameState, cancel: CancelToken) {
+        self.reader.read_until_cancelled(&cancel);
+        self.callbacks.push(Callback::WhileWaiting {
+            turn: state.turn,
+            cancel: cancel.reason(),
+        });
+        self.inner.while_waiting(state, cancel)
+    }
+}
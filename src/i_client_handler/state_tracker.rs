@@ -0,0 +1,138 @@
+use std::fmt;
+
+use log::info;
+
+use crate::{
+    internal::{FieldDiff, GameState},
+    neutral::{Move, Team},
+};
+
+/// the server's memento does not match the state we computed locally
+/// points to a rule bug in `make_move` or `possible_moves`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Desync {
+    /// the move that was applied locally
+    pub mv: Move,
+    /// the state before `mv`
+    pub before: GameState,
+    /// `before` with `mv` applied locally
+    pub expected: GameState,
+    /// the state the server sent
+    pub received: GameState,
+    /// squares that differ between `expected` and `received`
+    pub diff: Vec<FieldDiff>,
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "desync in turn {} after {:?}, {} fields differ (expected -> received):",
+            self.received.turn,
+            self.mv,
+            self.diff.len()
+        )?;
+        for diff in &self.diff {
+            writeln!(f, "{}", diff)?;
+        }
+        Ok(())
+    }
+}
+
+/// one move between two consecutive mementos
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub mv: Move,
+    /// the team that made `mv`
+    pub team: Team,
+    /// true if `team` is not ours, false if it is or we don't know our team yet
+    pub by_opponent: bool,
+    /// true if the memento had no `last_move` and `mv` was inferred from the board
+    pub inferred: bool,
+    /// the state before `mv`
+    pub before: GameState,
+    pub desync: Option<Desync>,
+}
+
+/// keeps a local `GameState` next to the server's
+/// every memento is checked against the previous one with the move applied locally:
+/// our own sent move or the opponent's `last_move`, inferred from the board if it is missing
+#[derive(Debug, Clone, Default)]
+pub struct StateTracker {
+    state: Option<GameState>,
+    team: Option<Team>,
+    /// the move we sent last, applied when the next memento arrives
+    sent: Option<Move>,
+}
+
+impl StateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the last state received from the server
+    pub fn state(&self) -> Option<&GameState> {
+        self.state.as_ref()
+    }
+
+    pub fn set_team(&mut self, team: Team) {
+        self.team = Some(team);
+    }
+
+    /// remembers the move we just sent
+    pub fn sent_move(&mut self, mv: Move) {
+        self.sent = Some(mv);
+    }
+
+    /// forgets the local state, the next memento is taken as is
+    /// e.g. after a reconnect, when mementos might have been missed
+    pub fn reset(&mut self) {
+        self.state = None;
+        self.sent = None;
+    }
+
+    /// checks `received` against the locally advanced previous state and adopts it
+    /// mementos that are not exactly one turn after the previous one can't be checked,
+    /// neither can boards no unique legal move leads to, if `last_move` is missing
+    pub fn update(&mut self, received: &GameState) -> Option<Transition> {
+        let before = self.state.replace(received.clone())?;
+        if received.turn != before.turn + 1 {
+            return None;
+        }
+        let team = before.current_team();
+        let sent = if Some(team) == self.team {
+            self.sent.take()
+        } else {
+            None
+        };
+        let (mv, inferred) = match sent.or(received.last_move) {
+            Some(mv) => (mv, false),
+            None => match GameState::infer_move(&before, received) {
+                Ok(mv) => (mv, true),
+                Err(e) => {
+                    info!("could not infer the move of turn {}: {:?}", before.turn, e);
+                    return None;
+                }
+            },
+        };
+
+        let mut expected = before.clone();
+        expected.perform_move(mv);
+        let diff = expected.board.diff(&received.board);
+        let desync = (!diff.is_empty()).then(|| Desync {
+            mv,
+            before: before.clone(),
+            expected,
+            received: received.clone(),
+            diff,
+        });
+        Some(Transition {
+            mv,
+            team,
+            by_opponent: self.team.is_some_and(|ours| ours != team),
+            inferred,
+            before,
+            desync,
+        })
+    }
+}
@@ -1,6 +1,57 @@
 #![allow(clippy::needless_late_init, unused_must_use)]
 // allowing needless_late_init due to warnings coming from macros inside the StrongXml crate
-use strong_xml::{XmlRead, XmlWrite};
+use std::io::Write;
+
+use strong_xml::{
+    xmlparser::{ElementEnd, Token},
+    XmlError, XmlRead, XmlReader, XmlResult, XmlWrite, XmlWriter,
+};
+
+/// an element that is kept as raw xml instead of being parsed
+/// used for the game specific parts of a message, which are parsed by the `Season`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawElement {
+    pub xml: String,
+}
+
+impl<'a> XmlRead<'a> for RawElement {
+    fn from_reader(reader: &mut XmlReader<'a>) -> XmlResult<Self> {
+        let mut xml = String::new();
+        let mut depth: usize = 0;
+        while let Some(token) = reader.next() {
+            match token? {
+                Token::ElementStart { span, .. } => {
+                    depth += 1;
+                    xml.push_str(span.as_str());
+                }
+                Token::Attribute { span, .. } => {
+                    xml.push(' ');
+                    xml.push_str(span.as_str());
+                }
+                Token::ElementEnd { end, span } => {
+                    xml.push_str(span.as_str());
+                    if !matches!(end, ElementEnd::Open) {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Ok(RawElement { xml });
+                        }
+                    }
+                }
+                Token::Text { text } => xml.push_str(text.as_str()),
+                Token::Cdata { span, .. } => xml.push_str(span.as_str()),
+                _ => {}
+            }
+        }
+        Err(XmlError::UnexpectedEof)
+    }
+}
+
+impl XmlWrite for RawElement {
+    fn to_writer<W: Write>(&self, writer: &mut XmlWriter<W>) -> XmlResult<()> {
+        writer.inner.write_all(self.xml.as_bytes())?;
+        Ok(())
+    }
+}
 
 #[derive(Debug, XmlRead, XmlWrite, Clone)]
 #[xml(tag = "field")]
@@ -162,8 +213,34 @@ pub struct ReceivedData {
     #[xml(child = "winner")]
     pub winner: Option<ReceivedWinner>,
 
+    /// game specific, parsed by the `Season` (e.g. into a `ReceivedState` for piranhas)
     #[xml(child = "state")]
-    pub state: Option<ReceivedState>,
+    pub state: Option<RawElement>,
+
+    /// only set for data with a class of welcomeMessage
+    #[xml(attr = "team")]
+    pub team: Option<String>,
+
+    /// only set for data with a class of welcomeMessage, used by older servers instead of team
+    #[xml(attr = "color")]
+    pub color: Option<String>,
+
+    /// only set for data with a class of error
+    #[xml(attr = "message")]
+    pub message: Option<String>,
+
+    /// only set for data with a class of error
+    #[xml(child = "originalMessage")]
+    pub original_message: Option<ReceivedOriginalMessage>,
+}
+
+/// the message that caused a room error, as echoed back by the server
+/// the content of the message is skipped, only its class is kept
+#[derive(Debug, XmlRead, XmlWrite)]
+#[xml(tag = "originalMessage")]
+pub struct ReceivedOriginalMessage {
+    #[xml(attr = "class")]
+    pub class: Option<String>,
 }
 
 #[derive(Debug, XmlRead, XmlWrite)]
@@ -200,6 +277,33 @@ pub struct ReceivedComMessage {
     pub room: Vec<ReceivedRoom>,
     #[xml(child = "prepared")]
     pub admin_prepared: Option<ReceivedAdminPrepared>,
+    #[xml(child = "observed")]
+    pub admin_observed: Option<ReceivedAdminObserved>,
+    #[xml(child = "errorpacket")]
+    pub error_packet: Option<ReceivedErrorPacket>,
+}
+
+// ___ errors ___
+
+#[derive(Debug, XmlRead, XmlWrite)]
+#[xml(tag = "errorpacket")]
+pub struct ReceivedErrorPacket {
+    #[xml(attr = "message")]
+    pub message: Option<String>,
+    #[xml(child = "originalRequest")]
+    pub original_request: Option<ReceivedOriginalRequest>,
+}
+
+/// the request that caused an error packet, as echoed back by the server
+#[derive(Debug, XmlRead, XmlWrite)]
+#[xml(tag = "originalRequest")]
+pub struct ReceivedOriginalRequest {
+    #[xml(attr = "class")]
+    pub class: Option<String>,
+    #[xml(attr = "roomId")]
+    pub room_id: Option<String>,
+    #[xml(attr = "reservationCode")]
+    pub reservation_code: Option<String>,
 }
 
 // ___ admin ____
@@ -219,3 +323,10 @@ pub struct ReceivedAdminReservation {
     #[xml(text)]
     pub reservation_id: String,
 }
+
+#[derive(Debug, XmlRead, XmlWrite)]
+#[xml(tag = "observed")]
+pub struct ReceivedAdminObserved {
+    #[xml(attr = "roomId")]
+    pub room_id: String,
+}
@@ -1,8 +1,12 @@
 use std::{fmt, str::FromStr};
 
+use log::info;
+
 use crate::{
-    incoming::{ReceivedBoard, ReceivedData, ReceivedRoom, ReceivedState},
+    error::InferMoveErr,
+    incoming::{ReceivedBoard, ReceivedData, ReceivedErrorPacket, ReceivedRoom, ReceivedState},
     neutral::{Direction, Move, PiranhaField, Team},
+    season::{piranhas::Piranhas, Season},
 };
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -70,6 +74,25 @@ impl TryFrom<ReceivedBoard> for Board {
     }
 }
 
+/// a square that differs between two boards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// (x, y)
+    pub pos: (u8, u8),
+    pub before: PiranhaField,
+    pub after: PiranhaField,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}): {} -> {}",
+            self.pos.0, self.pos.1, self.before, self.after
+        )
+    }
+}
+
 impl Board {
     pub fn in_bounds(x: i32, y: i32) -> bool {
         (0..10).contains(&x) && (0..10).contains(&y)
@@ -83,6 +106,24 @@ impl Board {
         &mut self.rows[y].fields[x]
     }
 
+    /// every square that is different on `other`, from bottom left to top right
+    pub fn diff(&self, other: &Board) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        for y in 0..10 {
+            for x in 0..10 {
+                let (before, after) = (*self.get(x, y), *other.get(x, y));
+                if before != after {
+                    diffs.push(FieldDiff {
+                        pos: (x as u8, y as u8),
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+        diffs
+    }
+
     pub fn count_fishes_on_axis(&self, x: usize, y: usize, dir: Direction) -> u8 {
         let (dx, dy) = dir.to_delta();
 
@@ -246,6 +287,8 @@ impl GameState {
             last_move: None,
         }
     }
+    // is_multiple_of needs rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
     pub fn current_team(&self) -> Team {
         if self.turn % 2 == 0 {
             match self.start_team {
@@ -261,8 +304,12 @@ impl GameState {
     }
 
     pub fn possible_moves(&self) -> Vec<Move> {
+        self.possible_moves_for(self.current_team())
+    }
+
+    /// the moves `team` could make on the current board, no matter whose turn it is
+    pub fn possible_moves_for(&self, team: Team) -> Vec<Move> {
         let mut moves = Vec::new();
-        let team = self.current_team();
 
         for y in 0..10 {
             for x in 0..10 {
@@ -299,6 +346,27 @@ impl GameState {
         moves
     }
 
+    /// summed fish size of `team`
+    pub fn swarm_size(&self, team: Team) -> u32 {
+        self.board
+            .rows
+            .iter()
+            .flat_map(|row| row.fields.iter())
+            .filter(|field| matches!(field, PiranhaField::Fish { team: t, .. } if *t == team))
+            .map(|field| field.to_size_num() as u32)
+            .sum()
+    }
+
+    /// simple evaluation from the view of `team`, positive is good for `team`
+    /// the swarm size difference, weighted by 10, plus the difference in legal moves
+    pub fn evaluate(&self, team: Team) -> i32 {
+        let opponent = team.opponent();
+        let material = self.swarm_size(team) as i32 - self.swarm_size(opponent) as i32;
+        let mobility = self.possible_moves_for(team).len() as i32
+            - self.possible_moves_for(opponent).len() as i32;
+        material * 10 + mobility
+    }
+
     pub fn get_field_type(&self, pos: (u8, u8)) -> Option<PiranhaField> {
         match self.board.rows.get(pos.1 as usize) {
             None => None,
@@ -318,6 +386,44 @@ impl GameState {
         fields
     }
 
+    /// the unique legal move of `before.current_team()` that turns the board of `before`
+    /// into the board of `after`. only the boards are compared, turn and last move are ignored
+    pub fn infer_move(before: &GameState, after: &GameState) -> Result<Move, InferMoveErr> {
+        let diff = before.board.diff(&after.board);
+        if diff.is_empty() {
+            return Err(InferMoveErr::Unchanged);
+        }
+        let mut state = before.clone();
+        let candidates: Vec<Move> = before
+            .possible_moves()
+            .into_iter()
+            // the moving fish leaves its square empty
+            .filter(|mv| {
+                diff.iter()
+                    .any(|d| d.pos == mv.from && d.after == PiranhaField::Empty)
+            })
+            .filter(|mv| {
+                let change = state.make_move(*mv);
+                let matches = state.board == after.board;
+                state.unmake_move(change);
+                matches
+            })
+            .collect();
+        match candidates.as_slice() {
+            [] => Err(InferMoveErr::NoLegalMove(diff)),
+            [mv] => Ok(*mv),
+            _ => Err(InferMoveErr::Ambiguous(candidates)),
+        }
+    }
+
+    /// makes the move like the server does: the board changes, `last_move` is set
+    /// and the turn advances. assumes legal move
+    pub fn perform_move(&mut self, mv: Move) {
+        self.make_move(mv);
+        self.last_move = Some(mv);
+        self.turn += 1;
+    }
+
     /// assumes legal move
     pub fn make_move(&mut self, mv: Move) -> MoveChange {
         let dis = Board::count_fishes_on_axis(
@@ -517,10 +623,34 @@ impl TryFrom<ReceivedData> for GameResult {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RoomMessage {
-    Memento(Box<GameState>),
+pub struct WelcomeMessage {
+    /// the team this client plays as, none if the server sent no team or an unknown one
+    pub team: Option<Team>,
+}
+
+impl From<&ReceivedData> for WelcomeMessage {
+    fn from(recv_data: &ReceivedData) -> Self {
+        let team_str = recv_data.team.as_ref().or(recv_data.color.as_ref());
+        let team = match team_str.map(|team| Team::try_from(team.to_uppercase().as_ref())) {
+            Some(Ok(team)) => Some(team),
+            Some(Err(e)) => {
+                info!("welcome message with an unknown team: {}", e);
+                None
+            }
+            None => {
+                info!("welcome message without a team");
+                None
+            }
+        };
+        WelcomeMessage { team }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RoomMessage<S: Season = Piranhas> {
+    Memento(Box<S::State>),
     Result(Box<GameResult>),
-    WelcomeMessage,
+    WelcomeMessage(WelcomeMessage),
     MoveRequest,
 }
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -529,23 +659,26 @@ pub struct PreparedRoom {
     pub room_id: String,
 }
 #[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Observed {
+    pub room_id: String,
+}
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum AdminMessage {
     /// (reservation, reservation)
     Prepared(PreparedRoom),
+    /// confirms an observe request
+    Observed(Observed),
 }
 
-impl TryFrom<ReceivedRoom> for RoomMessage {
-    type Error = String;
-    fn try_from(recv_room: ReceivedRoom) -> Result<Self, Self::Error> {
+impl<S: Season> RoomMessage<S> {
+    /// builds a room message, using `S` to parse the game state of mementos
+    pub fn from_received_room(recv_room: ReceivedRoom) -> Result<Self, String> {
         if let Some(data) = recv_room.data {
             if let Some(class) = &data.class {
                 match class.as_str() {
                     "memento" => {
-                        if let Some(state) = data.state {
-                            if state.class.as_ref() != Some(&"state".to_string()) {
-                                return Err("Received data with a class of memento should contain the a <state> element".to_string());
-                            }
-                            let state = GameState::try_from(state)?;
+                        if let Some(state) = &data.state {
+                            let state = S::parse_state(state)?;
                             Ok(RoomMessage::Memento(Box::new(state)))
                         } else {
                             Err("Received data with a class of memento should contain the ReceivedState".to_string())
@@ -558,7 +691,9 @@ impl TryFrom<ReceivedRoom> for RoomMessage {
                             Err("Received data with a class of result should contain the ReceivedResult".to_string())
                         }
                     }
-                    "welcomeMessage" => Ok(RoomMessage::WelcomeMessage),
+                    "welcomeMessage" => {
+                        Ok(RoomMessage::WelcomeMessage(WelcomeMessage::from(&data)))
+                    }
                     "moveRequest" => Ok(RoomMessage::MoveRequest),
                     other => Err(format!("unknown room message class '{}'", other)),
                 }
@@ -571,10 +706,113 @@ impl TryFrom<ReceivedRoom> for RoomMessage {
     }
 }
 
+impl TryFrom<ReceivedRoom> for RoomMessage {
+    type Error = String;
+    fn try_from(recv_room: ReceivedRoom) -> Result<Self, Self::Error> {
+        RoomMessage::from_received_room(recv_room)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerErrorReason {
+    /// the server rejected a move we sent
+    InvalidMove,
+    /// the reservation code used for `joinPrepared` is unknown or was already used
+    UnknownReservationCode,
+    /// admin authentication failed, usually because of a wrong password
+    AuthenticationFailed,
+    /// the request referenced a room that does not exist
+    UnknownRoom,
+    /// anything the client could not classify, check `ServerError::message`
+    Other,
+}
+
+impl ServerErrorReason {
+    /// classifies an error by the class of the request that caused it,
+    /// falling back to the message text
+    fn classify(original_class: Option<&str>, message: &str) -> Self {
+        match original_class {
+            Some("move") => return ServerErrorReason::InvalidMove,
+            Some("joinPrepared") => return ServerErrorReason::UnknownReservationCode,
+            Some("authenticate") => return ServerErrorReason::AuthenticationFailed,
+            _ => {}
+        }
+        let message = message.to_lowercase();
+        if message.contains("reservation") {
+            ServerErrorReason::UnknownReservationCode
+        } else if message.contains("password") || message.contains("authenticat") {
+            ServerErrorReason::AuthenticationFailed
+        } else if message.contains("move") || message.contains("zug") {
+            ServerErrorReason::InvalidMove
+        } else if message.contains("room") {
+            ServerErrorReason::UnknownRoom
+        } else {
+            ServerErrorReason::Other
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ServerError {
+    pub reason: ServerErrorReason,
+    /// the message as sent by the server
+    pub message: String,
+    /// class of the request that caused the error, if the server included it
+    pub original_request: Option<String>,
+    /// some if the error was sent inside of a room
+    pub room_id: Option<String>,
+}
+
+impl From<ReceivedErrorPacket> for ServerError {
+    fn from(recv_error: ReceivedErrorPacket) -> Self {
+        let message = recv_error.message.unwrap_or_default();
+        let original_request = recv_error.original_request.and_then(|req| req.class);
+        ServerError {
+            reason: ServerErrorReason::classify(original_request.as_deref(), &message),
+            message,
+            original_request,
+            room_id: None,
+        }
+    }
+}
+
+impl TryFrom<ReceivedRoom> for ServerError {
+    type Error = String;
+    fn try_from(recv_room: ReceivedRoom) -> Result<Self, Self::Error> {
+        let data = recv_room.data.ok_or("missing room message data")?;
+        if data.class.as_deref() != Some("error") {
+            return Err(format!(
+                "expected room message class 'error', got {:?}",
+                data.class
+            ));
+        }
+        let message = data.message.unwrap_or_default();
+        let original_request = data.original_message.and_then(|msg| msg.class);
+        Ok(ServerError {
+            reason: ServerErrorReason::classify(original_request.as_deref(), &message),
+            message,
+            original_request,
+            room_id: recv_room.room_id,
+        })
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.reason, self.message)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub enum ComMessage {
+pub enum ComMessage<S: Season = Piranhas> {
     Joined(Joined),
     Left(Left),
-    Room(Box<RoomMessage>),
+    /// a message sent inside of the room `room_id`
+    Room {
+        room_id: String,
+        msg: Box<RoomMessage<S>>,
+    },
     Admin(AdminMessage),
+    /// error packets and room errors sent by the server
+    Error(ServerError),
 }
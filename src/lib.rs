@@ -1,7 +1,19 @@
+pub mod admin;
+pub mod debugger;
 pub mod error;
+pub mod i_admin_handler;
 pub mod i_client_handler;
 pub mod incoming;
 pub mod internal;
+#[cfg(feature = "tools")]
+pub mod mock_server;
 pub mod neutral;
+#[cfg(feature = "tools")]
+pub mod orchestrator;
 pub mod outgoing;
+#[cfg(feature = "tools")]
+pub mod package;
+pub mod season;
 pub mod socha_com;
+pub mod spectator;
+pub mod transcript;
@@ -0,0 +1,554 @@
+//! small stand-in for the game server, to run matches locally
+//!
+//! supports admin authentication, `prepare`, `observe`, `pause`, `step`, `cancel` and `joinPrepared`.
+//! moves are checked against `GameState::possible_moves` and applied with `GameState::make_move`.
+//! the game ends after `max_turns`, when a team can not move, or when a player sends
+//! an illegal move or none in time. the score is the summed fish size of each team,
+//! not the official swarm rules
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use log::info;
+use strong_xml::{utils::xml_escape, xmlparser, XmlRead};
+
+use crate::{
+    incoming::ReceivedState,
+    internal::GameState,
+    neutral::{Direction, Move, Team},
+    outgoing::{Authenticate, Cancel, JoinPrepared, Observe, OutgoingRoom, Pause, Prepare, Step},
+};
+
+/// start position used by `MockServerConfig::default`
+const START_STATE_XML: &str = r#"<state class="state" startTeam="ONE" turn="0"><board><row><field>EMPTY</field><field>TWO_M</field><field>TWO_S</field><field>TWO_L</field><field>TWO_S</field><field>TWO_L</field><field>TWO_L</field><field>TWO_M</field><field>TWO_S</field><field>EMPTY</field></row><row><field>ONE_M</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_S</field></row><row><field>ONE_S</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>SQUID</field><field>EMPTY</field><field>EMPTY</field><field>ONE_S</field></row><row><field>ONE_L</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_L</field></row><row><field>ONE_S</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_M</field></row><row><field>ONE_L</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_M</field></row><row><field>ONE_L</field><field>EMPTY</field><field>EMPTY</field><field>SQUID</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_S</field></row><row><field>ONE_M</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_S</field></row><row><field>ONE_S</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>EMPTY</field><field>ONE_L</field></row><row><field>EMPTY</field><field>TWO_S</field><field>TWO_S</field><field>TWO_L</field><field>TWO_M</field><field>TWO_M</field><field>TWO_S</field><field>TWO_S</field><field>TWO_L</field><field>EMPTY</field></row></board></state>"#;
+
+#[derive(Debug, Clone)]
+pub struct MockServerConfig {
+    /// admin password
+    pub password: String,
+    pub start_state: GameState,
+    /// the game ends once this turn is reached
+    pub max_turns: u32,
+    /// time a player has to answer a move request
+    pub move_timeout: Duration,
+}
+
+impl Default for MockServerConfig {
+    fn default() -> Self {
+        let start_state = ReceivedState::from_str(START_STATE_XML)
+            .map_err(|e| e.to_string())
+            .and_then(GameState::try_from)
+            .unwrap();
+        MockServerConfig {
+            password: "examplepassword".to_string(),
+            start_state,
+            max_turns: 60,
+            move_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+/// a running mock server, stops accepting connections when dropped
+pub struct MockServer {
+    addr: String,
+    stop: Arc<AtomicBool>,
+    accept_handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// binds to `bind_addr`, use port 0 to get a free port
+    pub fn spawn(bind_addr: &str, config: MockServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(bind_addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?.to_string();
+        let stop = Arc::new(AtomicBool::new(false));
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let config = Arc::new(config);
+
+        let stop_clone = stop.clone();
+        let accept_handle = thread::spawn(move || {
+            while !stop_clone.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        info!("mock server: connection from {}", peer);
+                        let state = state.clone();
+                        let config = config.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = serve_connection(stream, state, config) {
+                                info!("mock server: connection ended with {:?}", e);
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                    Err(e) => {
+                        info!("mock server: accept failed: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(MockServer {
+            addr,
+            stop,
+            accept_handle: Some(accept_handle),
+        })
+    }
+
+    /// the address the server is listening on
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(h) = self.accept_handle.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    rooms: HashMap<String, Arc<MockRoom>>,
+    /// reservation code -> (room id, team)
+    reservations: HashMap<String, (String, Team)>,
+    room_count: u32,
+}
+
+struct MockRoom {
+    room_id: String,
+    /// index 0 is team one
+    players: Mutex<[Option<TcpStream>; 2]>,
+    observers: Mutex<Vec<TcpStream>>,
+    move_tx: Sender<(Team, Move)>,
+    move_rx: Receiver<(Team, Move)>,
+    control_tx: Sender<RoomControl>,
+    control_rx: Receiver<RoomControl>,
+    /// prepared paused, the game only starts after both players joined anyway
+    paused: bool,
+}
+
+/// admin requests for a running room
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoomControl {
+    Pause(bool),
+    /// plays one move of a paused room
+    Step,
+    Cancel,
+}
+
+impl MockRoom {
+    fn send_to_player(&self, team: Team, xml: &str) {
+        if let Some(stream) = &mut self.players.lock().unwrap()[team_index(team)] {
+            let _ = stream.write_all(xml.as_bytes());
+        }
+    }
+
+    /// sends to both players and all observers
+    fn broadcast(&self, xml: &str) {
+        for stream in self.players.lock().unwrap().iter_mut().flatten() {
+            let _ = stream.write_all(xml.as_bytes());
+        }
+        self.observers
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| stream.write_all(xml.as_bytes()).is_ok());
+    }
+
+    fn room_xml(&self, data_xml: &str) -> String {
+        format!(
+            "<room roomId=\"{}\">{}</room>",
+            xml_escape(&self.room_id),
+            data_xml
+        )
+    }
+}
+
+fn team_index(team: Team) -> usize {
+    match team {
+        Team::One => 0,
+        Team::Two => 1,
+    }
+}
+
+fn serve_connection(
+    mut stream: TcpStream,
+    state: Arc<Mutex<MockState>>,
+    config: Arc<MockServerConfig>,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.write_all(b"<protocol>")?;
+    let mut authenticated = false;
+    let mut player: Option<(Arc<MockRoom>, Team)> = None;
+    let mut buf = String::new();
+    let mut tmp = [0_u8; 4096];
+    loop {
+        let n = stream.read(&mut tmp)?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.push_str(&String::from_utf8_lossy(&tmp[..n]));
+        if let Some(rest) = buf.strip_prefix("<protocol>") {
+            buf = rest.to_string();
+        }
+        if buf.trim_start().starts_with("</protocol>") {
+            let _ = stream.shutdown(Shutdown::Both);
+            return Ok(());
+        }
+        while let Some((name, request)) = next_request(&mut buf) {
+            match name.as_str() {
+                "authenticate" => {
+                    let ok = Authenticate::from_str(&request)
+                        .is_ok_and(|auth| auth.password == config.password);
+                    if ok {
+                        authenticated = true;
+                    } else {
+                        stream.write_all(
+                            error_packet_xml("authenticate", "wrong password").as_bytes(),
+                        )?;
+                    }
+                }
+                "prepare" | "observe" | "pause" | "step" | "cancel" if !authenticated => {
+                    stream.write_all(error_packet_xml(&name, "not authenticated").as_bytes())?;
+                }
+                "prepare" => {
+                    let Ok(prepare) = Prepare::from_str(&request) else {
+                        stream.write_all(error_packet_xml(&name, "invalid request").as_bytes())?;
+                        continue;
+                    };
+                    let xml = prepare_room(&state, prepare.pause == "true");
+                    stream.write_all(xml.as_bytes())?;
+                }
+                "observe" => {
+                    let room = Observe::from_str(&request)
+                        .ok()
+                        .and_then(|o| state.lock().unwrap().rooms.get(&o.room_id).cloned());
+                    match room {
+                        Some(room) => {
+                            stream.write_all(
+                                format!("<observed roomId=\"{}\"/>", xml_escape(&room.room_id))
+                                    .as_bytes(),
+                            )?;
+                            room.observers.lock().unwrap().push(stream.try_clone()?);
+                        }
+                        None => {
+                            stream.write_all(error_packet_xml(&name, "unknown room").as_bytes())?
+                        }
+                    }
+                }
+                "pause" | "step" | "cancel" => {
+                    let control = match name.as_str() {
+                        "pause" => Pause::from_str(&request)
+                            .ok()
+                            .map(|p| (p.room_id, RoomControl::Pause(p.pause == "true"))),
+                        "step" => Step::from_str(&request)
+                            .ok()
+                            .map(|s| (s.room_id, RoomControl::Step)),
+                        _ => Cancel::from_str(&request)
+                            .ok()
+                            .map(|c| (c.room_id, RoomControl::Cancel)),
+                    };
+                    let room = control.and_then(|(room_id, control)| {
+                        let room = state.lock().unwrap().rooms.get(&room_id).cloned()?;
+                        Some((room, control))
+                    });
+                    match room {
+                        Some((room, control)) => {
+                            let _ = room.control_tx.send(control);
+                        }
+                        None => {
+                            stream.write_all(error_packet_xml(&name, "unknown room").as_bytes())?
+                        }
+                    }
+                }
+                "joinPrepared" => {
+                    let joined = JoinPrepared::from_str(&request)
+                        .ok()
+                        .and_then(|j| join_prepared(&state, &config, &stream, &j.reservation_code));
+                    match joined {
+                        Some(joined) => player = Some(joined),
+                        None => stream.write_all(
+                            error_packet_xml(&name, "unknown reservation code").as_bytes(),
+                        )?,
+                    }
+                }
+                "room" => {
+                    let Some((room, team)) = &player else {
+                        continue;
+                    };
+                    if let Ok(mv) = parse_move(&request) {
+                        let _ = room.move_tx.send((*team, mv));
+                    }
+                }
+                "close" => {
+                    let _ = stream.write_all(b"</protocol>");
+                    let _ = stream.shutdown(Shutdown::Both);
+                    return Ok(());
+                }
+                other => {
+                    stream.write_all(error_packet_xml(other, "unsupported request").as_bytes())?;
+                }
+            }
+        }
+    }
+}
+
+/// removes the first complete top level element from `buf`
+/// returns its tag name and its xml
+fn next_request(buf: &mut String) -> Option<(String, String)> {
+    let mut depth = 0_usize;
+    let mut name = None;
+    let mut start = None;
+    for token in xmlparser::Tokenizer::from(buf.as_str()) {
+        match token.ok()? {
+            xmlparser::Token::ElementStart { local, span, .. } => {
+                if depth == 0 {
+                    name = Some(local.as_str().to_string());
+                    start = Some(span.start());
+                }
+                depth += 1;
+            }
+            xmlparser::Token::ElementEnd { end, span }
+                if !matches!(end, xmlparser::ElementEnd::Open) =>
+            {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    let request = buf[start?..span.end()].to_string();
+                    buf.drain(..span.end());
+                    return Some((name?, request));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn error_packet_xml(original_class: &str, message: &str) -> String {
+    format!(
+        "<errorpacket message=\"{}\"><originalRequest class=\"{}\"/></errorpacket>",
+        xml_escape(message),
+        xml_escape(original_class)
+    )
+}
+
+fn prepare_room(state: &Mutex<MockState>, paused: bool) -> String {
+    let mut state = state.lock().unwrap();
+    state.room_count += 1;
+    let room_id = format!("mock-room-{}", state.room_count);
+    let reservations = [format!("{}-one", room_id), format!("{}-two", room_id)];
+    let (move_tx, move_rx) = unbounded();
+    let (control_tx, control_rx) = unbounded();
+    state.rooms.insert(
+        room_id.clone(),
+        Arc::new(MockRoom {
+            room_id: room_id.clone(),
+            players: Mutex::new([None, None]),
+            observers: Mutex::new(Vec::new()),
+            move_tx,
+            move_rx,
+            control_tx,
+            control_rx,
+            paused,
+        }),
+    );
+    state
+        .reservations
+        .insert(reservations[0].clone(), (room_id.clone(), Team::One));
+    state
+        .reservations
+        .insert(reservations[1].clone(), (room_id.clone(), Team::Two));
+    format!(
+        "<prepared roomId=\"{}\"><reservation>{}</reservation><reservation>{}</reservation></prepared>",
+        room_id, reservations[0], reservations[1]
+    )
+}
+
+/// seats the connection in the reserved room, starts the game once both players joined
+fn join_prepared(
+    state: &Mutex<MockState>,
+    config: &Arc<MockServerConfig>,
+    stream: &TcpStream,
+    reservation_code: &str,
+) -> Option<(Arc<MockRoom>, Team)> {
+    let mut state = state.lock().unwrap();
+    let (room_id, team) = state.reservations.remove(reservation_code)?;
+    let room = state.rooms.get(&room_id)?.clone();
+    drop(state);
+
+    let mut write_stream = stream.try_clone().ok()?;
+    let _ = write_stream.write_all(
+        format!(
+            "<joined roomId=\"{}\"/>{}",
+            xml_escape(&room.room_id),
+            room.room_xml(&format!(
+                "<data class=\"welcomeMessage\" team=\"{}\"/>",
+                team
+            ))
+        )
+        .as_bytes(),
+    );
+    let both_joined = {
+        let mut players = room.players.lock().unwrap();
+        players[team_index(team)] = Some(write_stream);
+        players.iter().all(|p| p.is_some())
+    };
+    if both_joined {
+        let room = room.clone();
+        let config = config.clone();
+        thread::spawn(move || run_game(&room, &config));
+    }
+    Some((room, team))
+}
+
+fn parse_move(request: &str) -> Result<Move, String> {
+    let room = OutgoingRoom::from_str(request).map_err(|e| e.to_string())?;
+    Ok(Move {
+        from: (room.data.from.x as u8, room.data.from.y as u8),
+        dir: Direction::try_from(room.data.direction.value.as_str())?,
+    })
+}
+
+fn run_game(room: &MockRoom, config: &MockServerConfig) {
+    let mut game_state = config.start_state.clone();
+    let mut paused = room.paused;
+    let winner = loop {
+        room.broadcast(&room.room_xml(&memento_xml(&game_state)));
+        let team = game_state.current_team();
+        let possible_moves = game_state.possible_moves();
+        if game_state.turn >= config.max_turns || possible_moves.is_empty() {
+            break None;
+        }
+        if !wait_for_next_move(room, &mut paused) {
+            close_cancelled(room);
+            return;
+        }
+        room.send_to_player(team, &room.room_xml("<data class=\"moveRequest\"/>"));
+        let mv = loop {
+            match room.move_rx.recv_timeout(config.move_timeout) {
+                Ok((from, mv)) if from == team => break Ok(mv),
+                // moves out of turn are ignored
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    break Err(format!("{} hat keinen Zug gesendet", team))
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        };
+        match mv {
+            Ok(mv) if possible_moves.contains(&mv) => {
+                game_state.perform_move(mv);
+            }
+            Ok(mv) => {
+                break Some((
+                    team.opponent(),
+                    format!("{} hat einen ungültigen Zug gesendet: {:?}", team, mv),
+                ))
+            }
+            Err(reason) => break Some((team.opponent(), reason)),
+        }
+    };
+    room.broadcast(&room.room_xml(&result_xml(&game_state, winner)));
+    let left = format!(
+        "<left roomId=\"{}\"/></protocol>",
+        xml_escape(&room.room_id)
+    );
+    room.send_to_player(Team::One, &left);
+    room.send_to_player(Team::Two, &left);
+    info!("mock server: room {} finished", room.room_id);
+}
+
+/// BLOCKING: while paused, waits until the room is stepped or resumed
+/// returns false if the room was cancelled
+fn wait_for_next_move(room: &MockRoom, paused: &mut bool) -> bool {
+    loop {
+        let control = if *paused {
+            match room.control_rx.recv() {
+                Ok(control) => control,
+                Err(_) => return false,
+            }
+        } else {
+            match room.control_rx.try_recv() {
+                Ok(control) => control,
+                Err(_) => return true,
+            }
+        };
+        match control {
+            RoomControl::Pause(pause) => *paused = pause,
+            RoomControl::Step if *paused => return true,
+            RoomControl::Step => {}
+            RoomControl::Cancel => return false,
+        }
+    }
+}
+
+/// ends a cancelled room without a result
+fn close_cancelled(room: &MockRoom) {
+    let left = format!("<left roomId=\"{}\"/>", xml_escape(&room.room_id));
+    room.observers
+        .lock()
+        .unwrap()
+        .retain_mut(|stream| stream.write_all(left.as_bytes()).is_ok());
+    let left = format!("{}</protocol>", left);
+    room.send_to_player(Team::One, &left);
+    room.send_to_player(Team::Two, &left);
+    info!("mock server: room {} cancelled", room.room_id);
+}
+
+fn memento_xml(state: &GameState) -> String {
+    let mut xml = format!(
+        "<data class=\"memento\"><state class=\"state\" startTeam=\"{}\" turn=\"{}\">",
+        state.start_team, state.turn
+    );
+    if let Some(mv) = state.last_move {
+        xml.push_str(&format!(
+            "<lastMove><from x=\"{}\" y=\"{}\"/><direction>{}</direction></lastMove>",
+            mv.from.0, mv.from.1, mv.dir
+        ));
+    }
+    xml.push_str("<board>");
+    for row in state.board.rows.iter() {
+        xml.push_str("<row>");
+        for field in row.fields.iter() {
+            xml.push_str(&format!("<field>{}</field>", field));
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</board></state></data>");
+    xml
+}
+
+/// `irregular_winner` is set if the game ended because of a player error
+fn result_xml(state: &GameState, irregular_winner: Option<(Team, String)>) -> String {
+    let (one, two) = (state.swarm_size(Team::One), state.swarm_size(Team::Two));
+    let winner = match irregular_winner {
+        Some((team, reason)) => format!(
+            "<winner team=\"{}\" regular=\"false\" reason=\"{}\"/>",
+            team,
+            xml_escape(&reason)
+        ),
+        None if one != two => format!(
+            "<winner team=\"{}\" regular=\"true\" reason=\"größerer Schwarm\"/>",
+            if one > two { Team::One } else { Team::Two }
+        ),
+        None => String::new(),
+    };
+    format!(
+        "<data class=\"result\"><definition><fragment name=\"Schwarmgröße\"><aggregation>AVERAGE</aggregation><relevantForRanking>true</relevantForRanking></fragment></definition><scores><entry><player team=\"ONE\"/><score><part>{}</part></score></entry><entry><player team=\"TWO\"/><score><part>{}</part></score></entry></scores>{}</data>",
+        one, two, winner
+    )
+}
@@ -0,0 +1,291 @@
+//! runs matches between bot executables
+//!
+//! a match prepares a room over the admin connection, observes it and starts both bots
+//! with the arguments the contest system uses (`--host <host> --port <port> --reservation <code>`).
+//! works against the real server and against `crate::mock_server::MockServer`
+
+use std::{
+    fs::File,
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use crate::{
+    error::{AdminErr, OrchestratorErr},
+    internal::GameResult,
+    neutral::Team,
+    season::{piranhas::Piranhas, GameType, Season},
+    socha_com::PrepareSlot,
+    spectator::{Spectator, SpectatorEvent},
+};
+
+/// how a bot is started, the connection arguments are appended to `args`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotCommand {
+    /// used as display name of the slot and in reports
+    pub name: String,
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    /// working directory of the bot, defaults to the current one
+    pub working_dir: Option<PathBuf>,
+}
+
+impl BotCommand {
+    pub fn new(name: &str, program: impl Into<PathBuf>) -> Self {
+        BotCommand {
+            name: name.to_string(),
+            program: program.into(),
+            args: Vec::new(),
+            working_dir: None,
+        }
+    }
+
+    /// starts the bot, output goes to `<log_dir>/<log_name>.log` if `log_dir` is set
+    pub fn spawn(
+        &self,
+        host: &str,
+        port: &str,
+        reservation: &str,
+        log_dir: Option<&PathBuf>,
+        log_name: &str,
+    ) -> Result<Child, OrchestratorErr> {
+        let spawn_err = |err| OrchestratorErr::SpawnBot {
+            program: self.program.display().to_string(),
+            err,
+        };
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args)
+            .args(["--host", host, "--port", port, "--reservation", reservation])
+            .stdin(Stdio::null());
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        match log_dir {
+            Some(dir) => {
+                let log = File::create(dir.join(format!("{}.log", log_name))).map_err(spawn_err)?;
+                cmd.stdout(log.try_clone().map_err(spawn_err)?).stderr(log);
+            }
+            None => {
+                cmd.stdout(Stdio::null()).stderr(Stdio::null());
+            }
+        }
+        cmd.spawn().map_err(spawn_err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchConfig {
+    /// `host:port` of the server
+    pub addr: String,
+    /// admin password
+    pub password: String,
+    pub game_type: GameType,
+    /// maximum duration of a single game
+    pub game_timeout: Duration,
+    /// how long the bots get to exit after the game, before they are killed
+    pub exit_grace: Duration,
+    /// if set, the output of every bot is written into this directory
+    pub log_dir: Option<PathBuf>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            addr: "localhost:13050".to_string(),
+            password: "examplepassword".to_string(),
+            game_type: Piranhas::game_type(),
+            game_timeout: Duration::from_secs(300),
+            exit_grace: Duration::from_secs(5),
+            log_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchReport {
+    pub room_id: String,
+    /// names of the bots as (team one, team two)
+    pub players: (String, String),
+    pub result: GameResult,
+    /// none if the bot had to be killed
+    pub exit_status: (Option<ExitStatus>, Option<ExitStatus>),
+}
+
+impl MatchReport {
+    /// name of the winning bot, none for a draw
+    pub fn winner_name(&self) -> Option<&str> {
+        self.result.winner.as_ref().map(|winner| match winner.team {
+            Team::One => self.players.0.as_str(),
+            Team::Two => self.players.1.as_str(),
+        })
+    }
+}
+
+/// outcome of `Orchestrator::run_batch`
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub matches: Vec<Result<MatchReport, OrchestratorErr>>,
+}
+
+impl BatchReport {
+    /// number of won games of the bot called `name`
+    pub fn wins(&self, name: &str) -> usize {
+        self.matches
+            .iter()
+            .flatten()
+            .filter(|report| report.winner_name() == Some(name))
+            .count()
+    }
+
+    pub fn draws(&self) -> usize {
+        self.matches
+            .iter()
+            .flatten()
+            .filter(|report| report.result.winner.is_none())
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.matches.iter().filter(|res| res.is_err()).count()
+    }
+}
+
+pub struct Orchestrator<S: Season = Piranhas> {
+    spectator: Spectator<S>,
+    config: MatchConfig,
+    host: String,
+    port: String,
+}
+
+impl<S: Season> Orchestrator<S> {
+    /// BLOCKING: connects to the server and authenticates as admin
+    pub fn connect(config: MatchConfig) -> Result<Self, OrchestratorErr> {
+        let (host, port) = config
+            .addr
+            .rsplit_once(':')
+            .map(|(host, port)| (host.to_string(), port.to_string()))
+            .ok_or_else(|| OrchestratorErr::InvalidAddr(config.addr.clone()))?;
+        let spectator = Spectator::connect(&config.addr, &config.game_type, &config.password)?;
+        Ok(Orchestrator {
+            spectator,
+            config,
+            host,
+            port,
+        })
+    }
+
+    /// BLOCKING: plays one game, `one` plays as team one
+    pub fn run_match(
+        &mut self,
+        one: &BotCommand,
+        two: &BotCommand,
+    ) -> Result<MatchReport, OrchestratorErr> {
+        let slots = [
+            PrepareSlot::new(one.name.clone(), true, true),
+            PrepareSlot::new(two.name.clone(), true, true),
+        ];
+        let prepared = self.spectator.admin().prepare(false, &slots)?;
+        let room_id = prepared.room_id;
+        self.spectator.observe(&room_id)?;
+        info!("prepared room {} for {} vs {}", room_id, one.name, two.name);
+
+        let log_dir = self.config.log_dir.as_ref();
+        let mut child_one = one.spawn(
+            &self.host,
+            &self.port,
+            &prepared.reservations.0,
+            log_dir,
+            &format!("{}-{}", room_id, one.name),
+        )?;
+        let mut child_two = match two.spawn(
+            &self.host,
+            &self.port,
+            &prepared.reservations.1,
+            log_dir,
+            &format!("{}-{}", room_id, two.name),
+        ) {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = child_one.kill();
+                let _ = child_one.wait();
+                return Err(e);
+            }
+        };
+
+        let result = self.wait_for_result(&room_id);
+        if result.is_err() {
+            let _ = self.spectator.admin().cancel(&room_id);
+        }
+        let exit_status = (
+            wait_or_kill(&mut child_one, self.config.exit_grace),
+            wait_or_kill(&mut child_two, self.config.exit_grace),
+        );
+        Ok(MatchReport {
+            room_id,
+            players: (one.name.clone(), two.name.clone()),
+            result: result?,
+            exit_status,
+        })
+    }
+
+    /// BLOCKING: plays `games` games one after another, switching sides after every game
+    pub fn run_batch(&mut self, a: &BotCommand, b: &BotCommand, games: u32) -> BatchReport {
+        let mut report = BatchReport::default();
+        for game in 0..games {
+            let res = if game % 2 == 0 {
+                self.run_match(a, b)
+            } else {
+                self.run_match(b, a)
+            };
+            if let Err(e) = &res {
+                info!("game {} failed: {:?}", game + 1, e);
+            }
+            report.matches.push(res);
+        }
+        report
+    }
+
+    fn wait_for_result(&mut self, room_id: &str) -> Result<GameResult, OrchestratorErr> {
+        let start_t = Instant::now();
+        loop {
+            let remaining = self.config.game_timeout.saturating_sub(start_t.elapsed());
+            let timeout_err = || OrchestratorErr::GameTimeout {
+                room_id: room_id.to_string(),
+            };
+            if remaining.is_zero() {
+                return Err(timeout_err());
+            }
+            match self.spectator.next_event(remaining) {
+                Ok(SpectatorEvent::GameFinished {
+                    room_id: id,
+                    result,
+                }) if id == room_id => {
+                    self.spectator.tracker_mut().forget(room_id);
+                    return Ok(*result);
+                }
+                Ok(_) => {}
+                Err(AdminErr::NoResponse(_)) => return Err(timeout_err()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// waits up to `grace` for the child to exit, kills it afterwards
+fn wait_or_kill(child: &mut Child, grace: Duration) -> Option<ExitStatus> {
+    let start_t = Instant::now();
+    while start_t.elapsed() < grace {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(_) => break,
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    None
+}
@@ -82,6 +82,15 @@ pub fn make_close_xml() -> String {
     "<close></close>".to_string()
 }
 
+/// wraps a `<data>` element built by a `Season` into a `<room>` element
+pub fn make_room_xml(room_id: &str, data_xml: &str) -> String {
+    format!(
+        "<room roomId=\"{}\">{}</room>",
+        strong_xml::utils::xml_escape(room_id),
+        data_xml
+    )
+}
+
 pub fn make_move_xml(
     room_id: &str,
     x: u32,
@@ -167,7 +176,7 @@ pub struct Prepare {
     pub slots: Vec<Slot>,
 }
 
-/// helpers
+// helpers
 
 pub fn make_authenticate_xml(password: &str) -> Result<String, Box<dyn Error>> {
     let auth = Authenticate {
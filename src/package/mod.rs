@@ -0,0 +1,345 @@
+//! packaging bots for the upload to the contest system
+//!
+//! the uploaded zip contains a start script at its root, the release executable
+//! and any extra files (configs, opening books). the contest system runs the start script
+//! with `--host`, `--port` and `--reservation`, which are passed on to the executable.
+//! `verify_package` unpacks a zip and lets it play a game on `crate::mock_server::MockServer`
+
+pub mod zip;
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Component, Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use log::info;
+
+use crate::{
+    error::PackageErr,
+    mock_server::{MockServer, MockServerConfig},
+    orchestrator::{BotCommand, MatchConfig, MatchReport, Orchestrator},
+    package::zip::{read_zip, write_zip, ZipEntry},
+};
+
+/// name of the start script inside of the zip
+pub const START_SCRIPT_NAME: &str = "start.sh";
+
+/// which cargo target is the bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildTarget {
+    Bin(String),
+    Example(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageConfig {
+    /// directory of the bot's Cargo.toml
+    pub manifest_dir: PathBuf,
+    pub target: BuildTarget,
+    /// files or directories placed next to the executable, under their own name
+    pub extra_files: Vec<PathBuf>,
+    /// where the zip is written to
+    pub out_path: PathBuf,
+    /// passed to cargo as `--target`, e.g. `x86_64-unknown-linux-gnu`
+    /// none builds for the host, which only runs on the contest system if the host is a linux
+    /// of the same architecture
+    pub target_triple: Option<String>,
+}
+
+/// the start script for an executable called `executable_name`
+pub fn start_script(executable_name: &str) -> String {
+    format!(
+        "#!/bin/sh\n\
+         # generated by socha::package, passes --host, --port and --reservation on to the bot\n\
+         cd \"$(dirname \"$0\")\"\n\
+         chmod +x ./{0} 2>/dev/null\n\
+         exec ./{0} \"$@\"\n",
+        executable_name
+    )
+}
+
+/// BLOCKING: builds the release binary and writes the zip
+/// returns the path of the zip
+pub fn package(config: &PackageConfig) -> Result<PathBuf, PackageErr> {
+    let executable = build_release(
+        &config.manifest_dir,
+        &config.target,
+        config.target_triple.as_deref(),
+    )?;
+    write_package(&executable, &config.extra_files, &config.out_path)?;
+    Ok(config.out_path.clone())
+}
+
+/// BLOCKING: runs `cargo build --release` for `target` and returns the path of the executable
+/// `target_triple` is passed as `--target`, none builds for the host
+pub fn build_release(
+    manifest_dir: &Path,
+    target: &BuildTarget,
+    target_triple: Option<&str>,
+) -> Result<PathBuf, PackageErr> {
+    let (kind_flag, name, sub_dir) = match target {
+        BuildTarget::Bin(name) => ("--bin", name, None),
+        BuildTarget::Example(name) => ("--example", name, Some("examples")),
+    };
+    let mut args = vec!["build", "--release", kind_flag, name];
+    if let Some(triple) = target_triple {
+        args.extend(["--target", triple]);
+    }
+    info!("running cargo {}", args.join(" "));
+    let status = Command::new(cargo())
+        .current_dir(manifest_dir)
+        .args(&args)
+        .status()?;
+    if !status.success() {
+        return Err(PackageErr::Build(format!(
+            "cargo {} failed with {}",
+            args.join(" "),
+            status
+        )));
+    }
+
+    let mut executable = target_dir(manifest_dir)?;
+    if let Some(triple) = target_triple {
+        executable.push(triple);
+    }
+    executable.push("release");
+    if let Some(sub_dir) = sub_dir {
+        executable.push(sub_dir);
+    }
+    executable.push(format!("{}{}", name, exe_suffix(target_triple)));
+    if !executable.is_file() {
+        return Err(PackageErr::Build(format!(
+            "built executable not found at {}",
+            executable.display()
+        )));
+    }
+    Ok(executable)
+}
+
+/// BLOCKING: the target directory of the package in `manifest_dir`, as reported by
+/// `cargo metadata`. this honors `CARGO_TARGET_DIR`, `build.target-dir` and workspaces
+pub fn target_dir(manifest_dir: &Path) -> Result<PathBuf, PackageErr> {
+    let output = Command::new(cargo())
+        .current_dir(manifest_dir)
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()?;
+    if !output.status.success() {
+        return Err(PackageErr::Build(format!(
+            "cargo metadata failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| PackageErr::Build(format!("cargo metadata printed invalid json: {}", e)))?;
+    metadata["target_directory"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            PackageErr::Build("cargo metadata did not report a target directory".to_string())
+        })
+}
+
+/// the file name suffix of executables built for `target_triple`, the host's if none
+pub fn exe_suffix(target_triple: Option<&str>) -> &'static str {
+    match target_triple {
+        Some(triple) if triple.contains("windows") => ".exe",
+        Some(_) => "",
+        None => std::env::consts::EXE_SUFFIX,
+    }
+}
+
+/// writes the zip with start script, `executable` and `extra_files`
+pub fn write_package(
+    executable: &Path,
+    extra_files: &[PathBuf],
+    out_path: &Path,
+) -> Result<(), PackageErr> {
+    let executable_name = file_name(executable)?;
+    let mut entries = vec![
+        ZipEntry {
+            name: START_SCRIPT_NAME.to_string(),
+            mode: 0o755,
+            data: start_script(&executable_name).into_bytes(),
+        },
+        ZipEntry {
+            name: executable_name,
+            mode: 0o755,
+            data: fs::read(executable)?,
+        },
+    ];
+    for extra in extra_files {
+        add_path(&mut entries, extra, &file_name(extra)?)?;
+    }
+    check_layout(&entries)?;
+
+    let out = BufWriter::new(File::create(out_path)?);
+    write_zip(out, &entries)?;
+    info!("wrote {} with {} files", out_path.display(), entries.len());
+    Ok(())
+}
+
+/// checks that the start script is at the root, executable,
+/// and that the executable it starts is part of the package
+pub fn check_layout(entries: &[ZipEntry]) -> Result<(), PackageErr> {
+    let invalid = |msg: String| Err(PackageErr::InvalidPackage(msg));
+    let Some(script) = entries.iter().find(|e| e.name == START_SCRIPT_NAME) else {
+        return invalid(format!("{} is missing at the root", START_SCRIPT_NAME));
+    };
+    if script.mode & 0o111 == 0 {
+        return invalid(format!("{} is not executable", START_SCRIPT_NAME));
+    }
+    let script_text = String::from_utf8_lossy(&script.data);
+    if !script_text.starts_with("#!") {
+        return invalid(format!("{} has no shebang line", START_SCRIPT_NAME));
+    }
+    if script_text.contains('\r') {
+        return invalid(format!("{} has windows line endings", START_SCRIPT_NAME));
+    }
+    let Some(executable) = script_text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("exec ./"))
+        .and_then(|rest| rest.split_whitespace().next())
+    else {
+        return invalid(format!(
+            "{} does not exec a bundled file",
+            START_SCRIPT_NAME
+        ));
+    };
+    match entries.iter().find(|e| e.name == executable) {
+        Some(entry) if entry.mode & 0o111 != 0 => {}
+        Some(_) => return invalid(format!("{} is not executable", executable)),
+        None => return invalid(format!("{} is started but not packaged", executable)),
+    }
+    let mut names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    names.sort_unstable();
+    if let Some(dup) = names.windows(2).find(|w| w[0] == w[1]) {
+        return invalid(format!("{} is packaged twice", dup[0]));
+    }
+    Ok(())
+}
+
+/// unpacks `zip_path` into `dir`, checks its layout and returns the path of the start script
+pub fn unpack(zip_path: &Path, dir: &Path) -> Result<PathBuf, PackageErr> {
+    let entries = read_zip(&fs::read(zip_path)?).map_err(PackageErr::InvalidPackage)?;
+    check_layout(&entries)?;
+    // all names are checked before anything is written
+    if let Some(entry) = entries.iter().find(|entry| {
+        !Path::new(&entry.name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    }) {
+        return Err(PackageErr::InvalidPackage(format!(
+            "{} points outside of the package",
+            entry.name
+        )));
+    }
+    for entry in &entries {
+        let path = dir.join(&entry.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &entry.data)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(entry.mode))?;
+        }
+    }
+    Ok(dir.join(START_SCRIPT_NAME))
+}
+
+/// BLOCKING: unpacks `zip_path` into `work_dir` and lets the start script play
+/// against itself on a mock server, like the contest system would start it
+/// the output of both bots is written into `work_dir`
+pub fn verify_package(zip_path: &Path, work_dir: &Path) -> Result<MatchReport, PackageErr> {
+    fs::create_dir_all(work_dir)?;
+    let start_script = unpack(zip_path, work_dir)?;
+
+    let mock = MockServer::spawn("127.0.0.1:0", MockServerConfig::default())?;
+    let config = MatchConfig {
+        addr: mock.addr().to_string(),
+        password: MockServerConfig::default().password,
+        game_timeout: Duration::from_secs(300),
+        log_dir: Some(work_dir.to_path_buf()),
+        ..Default::default()
+    };
+    let mut bot = BotCommand::new("package", start_script);
+    bot.working_dir = Some(work_dir.to_path_buf());
+
+    let mut orchestrator: Orchestrator = Orchestrator::connect(config)?;
+    let report = orchestrator.run_match(&bot, &bot)?;
+
+    let logs = work_dir.display();
+    for status in [report.exit_status.0, report.exit_status.1] {
+        match status {
+            Some(status) if status.success() => {}
+            Some(status) => {
+                return Err(PackageErr::Verify(format!(
+                    "the start script exited with {}, see the logs in {}",
+                    status, logs
+                )))
+            }
+            None => {
+                return Err(PackageErr::Verify(format!(
+                    "the bot did not exit after the game, see the logs in {}",
+                    logs
+                )))
+            }
+        }
+    }
+    if let Some(winner) = report.result.winner.as_ref().filter(|w| !w.regular) {
+        return Err(PackageErr::Verify(format!(
+            "the game ended irregularly: {}, see the logs in {}",
+            winner.reason.as_deref().unwrap_or("no reason given"),
+            logs
+        )));
+    }
+    Ok(report)
+}
+
+fn cargo() -> std::ffi::OsString {
+    std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into())
+}
+
+fn file_name(path: &Path) -> Result<String, PackageErr> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| PackageErr::InvalidPackage(format!("{} has no file name", path.display())))
+}
+
+/// adds the file at `path`, or everything below it for directories, as `name`
+fn add_path(entries: &mut Vec<ZipEntry>, path: &Path, name: &str) -> Result<(), PackageErr> {
+    if path.is_dir() {
+        let mut children: Vec<_> = fs::read_dir(path)?.collect::<Result<_, _>>()?;
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+            add_path(entries, &child.path(), &child_name)?;
+        }
+        return Ok(());
+    }
+    if !path.is_file() {
+        return Err(PackageErr::MissingFile(path.to_path_buf()));
+    }
+    entries.push(ZipEntry {
+        name: name.to_string(),
+        mode: file_mode(path)?,
+        data: fs::read(path)?,
+    });
+    Ok(())
+}
+
+#[cfg(unix)]
+fn file_mode(path: &Path) -> Result<u32, PackageErr> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(path)?.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> Result<u32, PackageErr> {
+    Ok(0o644)
+}
@@ -0,0 +1,61 @@
+//! reading and writing the package archive with the `zip` crate
+//!
+//! enough for packaging a bot: file names, data and unix permissions
+
+use std::io::{Cursor, Read, Seek, Write};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::PackageErr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    /// path inside of the archive, separated by `/`
+    pub name: String,
+    /// unix permission bits, e.g. 0o755
+    pub mode: u32,
+    pub data: Vec<u8>,
+}
+
+/// writes `entries` as a deflated zip archive
+/// entries of 4 GiB and more are written as zip64, names longer than 65535 bytes
+/// fail with `PackageErr::TooLarge`
+pub fn write_zip<W: Write + Seek>(out: W, entries: &[ZipEntry]) -> Result<(), PackageErr> {
+    let mut zip = ZipWriter::new(out);
+    for entry in entries {
+        // the zip crate panics on longer names
+        if u16::try_from(entry.name.len()).is_err() {
+            return Err(PackageErr::TooLarge(format!("the name of {}", entry.name)));
+        }
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(entry.mode)
+            .large_file(u32::try_from(entry.data.len()).is_err());
+        zip.start_file(entry.name.as_str(), options)?;
+        zip.write_all(&entry.data)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// reads the files of a zip archive, directory entries are skipped
+/// the checksum of every file is verified
+pub fn read_zip(bytes: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .map_err(|e| format!("{} could not be read: {}", file.name(), e))?;
+        entries.push(ZipEntry {
+            name: file.name().to_string(),
+            mode: file.unix_mode().unwrap_or(0o644) & 0o7777,
+            data,
+        });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,77 @@
+//! game specific parts of the protocol
+//!
+//! the transport (`ComHandler`) and the room handling are shared between all seasons,
+//! everything that changes from year to year lives behind the `Season` trait.
+//! a new season is added as a module next to `piranhas`
+//!
+//! only the protocol layer (`ComHandler`, `ComMessage`, `RoomMessage`, `transcript`), `AdminClient`
+//! and `Spectator` are generic over the season. the runtimes and tools built on them
+//! (`i_client_handler`, `i_admin_handler`, `debugger`, `mock_server`, `orchestrator`) play
+//! piranhas only
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::incoming::RawElement;
+
+pub mod piranhas;
+
+/// the game type used in `join` and `prepare`, e.g. `swc_2026_piranhas`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameType {
+    /// the year of the season, e.g. 2026
+    pub year: u16,
+    /// the name of the game, e.g. piranhas
+    pub name: String,
+}
+
+impl GameType {
+    pub fn new(year: u16, name: &str) -> Self {
+        GameType {
+            year,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GameType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "swc_{}_{}", self.year, self.name)
+    }
+}
+
+impl FromStr for GameType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, '_');
+        if parts.next() != Some("swc") {
+            return Err(format!("game type '{}' should start with 'swc_'", s));
+        }
+        let year = parts
+            .next()
+            .ok_or_else(|| format!("missing year in game type '{}'", s))?
+            .parse::<u16>()
+            .map_err(|e| format!("invalid year in game type '{}': {}", s, e))?;
+        let name = parts
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("missing name in game type '{}'", s))?;
+        Ok(GameType::new(year, name))
+    }
+}
+
+/// everything the shared protocol layer needs to know about the game of one season
+pub trait Season: fmt::Debug + Clone + PartialEq + Eq + Send + 'static {
+    /// the game state contained in a memento
+    type State: fmt::Debug + Clone + PartialEq + Eq + Send;
+    /// a move, as sent to the server
+    type Move: fmt::Debug + Clone + PartialEq + Eq + Send;
+
+    /// the game type this season is played with by default
+    fn game_type() -> GameType;
+
+    /// parses the `<state>` element of a memento
+    fn parse_state(raw_state: &RawElement) -> Result<Self::State, String>;
+
+    /// builds the `<data class="move">` element for a move
+    fn make_move_data_xml(mv: &Self::Move) -> Result<String, Box<dyn Error>>;
+}
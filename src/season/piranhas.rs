@@ -0,0 +1,51 @@
+//! Piranhas, the game of the software challenge 2026
+
+use std::error::Error;
+
+use strong_xml::{XmlRead, XmlWrite};
+
+use crate::{
+    incoming::{RawElement, ReceivedState},
+    outgoing::{DataMove, FromPos, OutgoingDirection},
+    season::{GameType, Season},
+};
+
+pub use crate::internal::{Board, GameState};
+pub use crate::neutral::{Direction, Move, PiranhaField, Size, Team};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Piranhas;
+
+impl Season for Piranhas {
+    type State = GameState;
+    type Move = Move;
+
+    fn game_type() -> GameType {
+        GameType::new(2026, "piranhas")
+    }
+
+    fn parse_state(raw_state: &RawElement) -> Result<Self::State, String> {
+        let state = ReceivedState::from_str(&raw_state.xml).map_err(|e| format!("{:?}", e))?;
+        if state.class.as_deref() != Some("state") {
+            return Err(
+                "Received data with a class of memento should contain the a <state> element"
+                    .to_string(),
+            );
+        }
+        GameState::try_from(state)
+    }
+
+    fn make_move_data_xml(mv: &Self::Move) -> Result<String, Box<dyn Error>> {
+        let data = DataMove {
+            class: "move".to_string(),
+            from: FromPos {
+                x: mv.from.0.into(),
+                y: mv.from.1.into(),
+            },
+            direction: OutgoingDirection {
+                value: mv.dir.to_string(),
+            },
+        };
+        Ok(data.to_string()?)
+    }
+}
@@ -1,83 +1,309 @@
-use crate::error::{ComMessageBuildErr, ConnectionClosedErr, ReceiveErr, SendErr};
+use crate::error::{
+    ComError, ComMessageBuildErr, ConnectionClosedErr, ReceiveErr, RejoinErr, SendErr,
+};
 
 use crate::incoming::{ReceivedComMessage, ReceivedRoom};
-use crate::internal::{AdminMessage, ComMessage, Joined, Left, PreparedRoom, RoomMessage};
-use crate::neutral::Direction;
-use crate::outgoing::{make_join_prepared_xml, make_join_xml, make_move_xml};
+use crate::internal::{
+    AdminMessage, ComMessage, Joined, Left, Observed, PreparedRoom, RoomMessage, ServerError,
+};
+use crate::outgoing::{make_close_xml, make_join_prepared_xml, make_join_xml, make_room_xml};
+use crate::season::{piranhas::Piranhas, GameType, Season};
+use crate::transcript::{TrafficDirection, TranscriptRecorder};
 use log::info;
 use std::io::{self, BufReader, Read, Write};
-use std::net::TcpStream;
-use std::time::Duration;
+use std::marker::PhantomData;
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use strong_xml::XmlRead;
 
 /// Connection helper for the Software-Challenge XML protocol
-pub struct ComHandler {
+/// `S` is the season whose game is played, the transport itself is the same for every season
+pub struct ComHandler<S: Season = Piranhas> {
     reader: BufReader<TcpStream>,
+    /// how the socket currently reads, none if unknown
+    read_mode: Option<ReadMode>,
     buf: String,
-    stream: TcpStream,
+    /// the start of a character split between two reads, completed by the next read
+    undecoded: Vec<u8>,
+    /// shares the socket for writing, see `sender`
+    writer: ComSender<S>,
+    /// the address and reservation code used to join, needed to reconnect
+    addr: String,
+    reservation_code: Option<String>,
     pub room_id: Option<String>,
-    msgs: Vec<ComMessage>,
+    msgs: Vec<ComMessage<S>>,
     protocol_tag_found: bool,
+    /// the server sent `</protocol>`
+    protocol_ended: bool,
+    /// the server closed the connection
+    server_closed: bool,
 }
 
-impl ComHandler {
-    /// BLOCKING: connect to `addr` and join a free room.
+/// how long a single read may block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadMode {
+    NonBlocking,
+    Timeout(Duration),
+    Blocking,
+}
+
+/// how long `close` and `Drop` wait for the server to finish sending
+pub const DEFAULT_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl<S: Season> ComHandler<S> {
+    /// BLOCKING: connect to `addr` and join a free room of `S::game_type()`
     pub fn join(addr: &str, opt_reservation_code: Option<&str>) -> Result<Self, ReceiveErr> {
+        Self::join_with_game_type(addr, opt_reservation_code, &S::game_type())
+    }
+
+    /// BLOCKING: connect to `addr` and join a free room of `game_type`
+    /// `game_type` is ignored when joining with a reservation code
+    pub fn join_with_game_type(
+        addr: &str,
+        opt_reservation_code: Option<&str>,
+        game_type: &GameType,
+    ) -> Result<Self, ReceiveErr> {
+        Self::join_with_policy(
+            addr,
+            opt_reservation_code,
+            game_type,
+            None,
+            &ReconnectPolicy::disabled(),
+        )
+    }
+
+    /// BLOCKING: same as `join`, but writes all traffic, including the handshake,
+    /// into the transcript of `recorder`
+    pub fn join_recorded(
+        addr: &str,
+        opt_reservation_code: Option<&str>,
+        game_type: &GameType,
+        recorder: TranscriptRecorder,
+    ) -> Result<Self, ReceiveErr> {
+        Self::join_with_policy(
+            addr,
+            opt_reservation_code,
+            game_type,
+            Some(recorder),
+            &ReconnectPolicy::disabled(),
+        )
+    }
+
+    /// BLOCKING: same as `join`, but retries connecting with the backoff of `policy`
+    /// `recorder` is optional, see `join_recorded`
+    pub fn join_with_policy(
+        addr: &str,
+        opt_reservation_code: Option<&str>,
+        game_type: &GameType,
+        mut recorder: Option<TranscriptRecorder>,
+        policy: &ReconnectPolicy,
+    ) -> Result<Self, ReceiveErr> {
+        let mut attempt = 0;
+        loop {
+            match Self::try_join(addr, opt_reservation_code, game_type, &mut recorder) {
+                Ok(com) => return Ok(com),
+                Err(e) if attempt < policy.max_attempts => {
+                    info!("joining failed, retrying: {:?}", e);
+                    std::thread::sleep(policy.backoff(attempt));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// takes the recorder out of `recorder` only if joining succeeds
+    fn try_join(
+        addr: &str,
+        opt_reservation_code: Option<&str>,
+        game_type: &GameType,
+        recorder: &mut Option<TranscriptRecorder>,
+    ) -> Result<Self, ReceiveErr> {
         println!("connecting to {}", addr);
-        let mut stream = TcpStream::connect(addr)?;
-        stream.set_nonblocking(true)?;
+        let stream = TcpStream::connect(addr)?;
+        let mut com = Self::from_stream(stream, addr, game_type, recorder.take())?;
+        com.reservation_code = opt_reservation_code.map(|s| s.to_string());
 
         let join_xml = if let Some(res_code) = opt_reservation_code {
             make_join_prepared_xml(res_code).unwrap()
         } else {
-            make_join_xml("swc_2026_piranhas", None).unwrap()
+            make_join_xml(&game_type.to_string(), None).unwrap()
         };
-        stream.write_all(b"<protocol>")?;
-        stream.write_all(join_xml.as_bytes())?;
-        stream.flush()?;
-
-        let reader: BufReader<TcpStream> = BufReader::new(stream.try_clone()?);
-        let mut com = ComHandler {
-            reader,
-            buf: String::new(),
-            stream,
-            room_id: None,
-            msgs: Vec::new(),
-            protocol_tag_found: false,
-        };
-
-        com.wait_for_and_rm_str("<protocol>")?;
+        let handshake = com
+            .write_out(&format!("<protocol>{}", join_xml))
+            .map_err(ReceiveErr::from)
+            .and_then(|_| com.wait_for_and_rm_str("<protocol>"));
+        if let Err(e) = handshake {
+            *recorder = com.writer.take_recorder();
+            return Err(e);
+        }
         com.protocol_tag_found = true;
         Ok(com)
     }
+
+    /// BLOCKING: reconnect after the connection dropped and rejoin the room
+    /// with the reservation code used in `join`
+    /// messages that were received before the connection dropped are kept
+    /// the server decides whether a reservation code can be used again,
+    /// if it refuses `RejoinErr::Rejected` is returned right away
+    pub fn reconnect(&mut self, policy: &ReconnectPolicy) -> Result<(), ReceiveErr> {
+        let reservation_code = self
+            .reservation_code
+            .clone()
+            .ok_or(ReceiveErr::RejoinFailed(RejoinErr::NoReservationCode))?;
+        let mut last_err = None;
+        for attempt in 0..policy.max_attempts {
+            std::thread::sleep(policy.backoff(attempt));
+            info!("reconnect attempt {} to {}", attempt + 1, self.addr);
+            match self.try_rejoin(&reservation_code, policy.rejoin_timeout) {
+                Ok(()) => return Ok(()),
+                Err(e @ ReceiveErr::RejoinFailed(_)) => return Err(e),
+                Err(e) => {
+                    info!("reconnect attempt failed: {:?}", e);
+                    last_err = Some(Box::new(e));
+                }
+            }
+        }
+        Err(ReceiveErr::RejoinFailed(RejoinErr::GaveUp {
+            attempts: policy.max_attempts,
+            last_err,
+        }))
+    }
+
+    fn try_rejoin(&mut self, reservation_code: &str, timeout: Duration) -> Result<(), ReceiveErr> {
+        let stream = TcpStream::connect(&self.addr)?;
+        let old_stream = self.writer.replace_stream(stream.try_clone()?);
+        let _ = old_stream.shutdown(Shutdown::Both);
+        self.reader = BufReader::new(stream);
+        self.read_mode = None;
+        self.buf.clear();
+        self.undecoded.clear();
+        self.protocol_tag_found = false;
+        self.protocol_ended = false;
+        self.server_closed = false;
+
+        let join_xml = make_join_prepared_xml(reservation_code).unwrap();
+        self.write_out(&format!("<protocol>{}", join_xml))?;
+
+        // messages from before the drop stay in front
+        let pending = std::mem::take(&mut self.msgs);
+        let mut received = Vec::new();
+        let start_t = Instant::now();
+        let res = loop {
+            let remaining = timeout.saturating_sub(start_t.elapsed());
+            match self.wait_for_com_message(remaining) {
+                Ok(ComMessage::Joined(_)) => break Ok(()),
+                Ok(ComMessage::Error(err)) => {
+                    break Err(ReceiveErr::RejoinFailed(RejoinErr::Rejected(err)))
+                }
+                Ok(msg) => received.push(msg),
+                Err(e) => break Err(e),
+            }
+        };
+        received.append(&mut self.msgs);
+        self.msgs = pending;
+        self.msgs.append(&mut received);
+        res
+    }
+
     /// BLOCKING: connect to `addr`
     /// usually used for an admin client
     /// not recommeded when writing a bot-player
+    /// rooms are prepared with `S::game_type()`
     pub fn connect_to_server(addr: &str) -> Result<Self, ReceiveErr> {
+        Self::connect_to_server_with_game_type(addr, &S::game_type())
+    }
+
+    /// BLOCKING: same as `connect_to_server`, rooms are prepared with `game_type`
+    pub fn connect_to_server_with_game_type(
+        addr: &str,
+        game_type: &GameType,
+    ) -> Result<Self, ReceiveErr> {
         println!("connecting to {}", addr);
-        let mut stream = TcpStream::connect(addr)?;
-        stream.set_nonblocking(false)?;
+        let stream = TcpStream::connect(addr)?;
+        let mut com = Self::from_stream(stream, addr, game_type, None)?;
 
-        stream.write_all(b"<protocol>")?;
-        stream.flush()?;
+        com.write_out("<protocol>")?;
+        Ok(com)
+    }
 
+    fn from_stream(
+        stream: TcpStream,
+        addr: &str,
+        game_type: &GameType,
+        recorder: Option<TranscriptRecorder>,
+    ) -> Result<Self, ReceiveErr> {
         let reader: BufReader<TcpStream> = BufReader::new(stream.try_clone()?);
-
         Ok(ComHandler {
             reader,
+            read_mode: None,
             buf: String::new(),
-            stream,
+            undecoded: Vec::new(),
+            writer: ComSender::new(stream, game_type, recorder),
+            addr: addr.to_string(),
+            reservation_code: None,
             room_id: None,
             msgs: Vec::new(),
             protocol_tag_found: false,
+            protocol_ended: false,
+            server_closed: false,
         })
     }
 
+    /// a handle to send from other threads, while this handler blocks on reading
+    /// it keeps working after a reconnect
+    pub fn sender(&self) -> ComSender<S> {
+        self.writer.clone()
+    }
+
+    /// start writing all further traffic into the transcript of `recorder`
+    /// use `join_recorded` to include the handshake
+    pub fn set_recorder(&mut self, recorder: TranscriptRecorder) {
+        *self.writer.shared.recorder.lock().unwrap() = Some(recorder);
+    }
+
+    /// writes to the server and records the chunk
+    fn write_out(&mut self, data: &str) -> io::Result<()> {
+        self.writer.write_out(data)
+    }
+
+    /// returns an error if there are no buffered messages left and the server ended the connection
+    fn check_connection_ended(&self) -> Result<(), ReceiveErr> {
+        if !self.msgs.is_empty() {
+            return Ok(());
+        }
+        if self.protocol_ended {
+            return Err(ReceiveErr::ConnectionClosed(
+                ConnectionClosedErr::ProtocolEnded,
+            ));
+        }
+        if self.server_closed {
+            return Err(ReceiveErr::ConnectionClosed(
+                ConnectionClosedErr::ServerClosed,
+            ));
+        }
+        Ok(())
+    }
+
     /// BLOCKING: wait until a `ComMessage` is available and return it.
-    pub fn wait_for_com_message(&mut self, max_time: Duration) -> Result<ComMessage, ReceiveErr> {
-        let res: Result<ComMessage, ReceiveErr>;
-        let start_t = std::time::Instant::now();
+    /// the socket blocks while waiting, no cpu time is spent
+    pub fn wait_for_com_message(
+        &mut self,
+        max_time: Duration,
+    ) -> Result<ComMessage<S>, ReceiveErr> {
+        self.receive(Some(max_time))
+    }
+
+    /// BLOCKING: wait without a time limit until a `ComMessage` is available and return it
+    pub fn recv_com_message(&mut self) -> Result<ComMessage<S>, ReceiveErr> {
+        self.receive(None)
+    }
+
+    fn receive(&mut self, max_time: Option<Duration>) -> Result<ComMessage<S>, ReceiveErr> {
+        let start_t = Instant::now();
         loop {
             let mut msgs = self.attempt_get_com_messages()?;
             self.msgs.append(&mut msgs);
@@ -85,27 +311,32 @@ impl ComHandler {
                 if cfg!(debug_assertions) {
                     info!("retrieving saved messages: {}", self.msgs.len());
                 }
-                res = Ok(self.msgs.remove(0));
-                break;
+                return Ok(self.msgs.remove(0));
             }
             if self.try_for_and_rm_str("<comMessage/>") {
-                res = Err(ReceiveErr::ConnectionClosed(
-                    ConnectionClosedErr::ProtocolEnded,
-                ));
-                break;
-            }
-            if start_t.elapsed() > max_time {
                 return Err(ReceiveErr::ConnectionClosed(
-                    ConnectionClosedErr::NoMessageReceivedFor(max_time),
+                    ConnectionClosedErr::ProtocolEnded,
                 ));
             }
-            self.try_read_new()?;
+            self.check_connection_ended()?;
+            let mode = match max_time {
+                Some(max_time) => {
+                    let remaining = max_time.saturating_sub(start_t.elapsed());
+                    if remaining.is_zero() {
+                        return Err(ReceiveErr::ConnectionClosed(
+                            ConnectionClosedErr::NoMessageReceivedFor(max_time),
+                        ));
+                    }
+                    ReadMode::Timeout(remaining)
+                }
+                None => ReadMode::Blocking,
+            };
+            self.read_new(mode)?;
         }
-        res
     }
 
     /// NONBLOCKING: try to read and return a `ComMessage` if available.
-    pub fn try_for_com_message(&mut self) -> Result<Option<ComMessage>, ReceiveErr> {
+    pub fn try_for_com_message(&mut self) -> Result<Option<ComMessage<S>>, ReceiveErr> {
         self.try_receive_com_message()?;
         if !self.msgs.is_empty() {
             if cfg!(debug_assertions) {
@@ -118,12 +349,13 @@ impl ComHandler {
                 ConnectionClosedErr::ProtocolEnded,
             ));
         }
+        self.check_connection_ended()?;
         Ok(None)
     }
 
     /// NONBLOCKING: tries to receive a new com message and stores it into the message buffer
     fn try_receive_com_message(&mut self) -> Result<(), ReceiveErr> {
-        self.try_read_new()?;
+        self.read_new(ReadMode::NonBlocking)?;
         let mut msgs = self.attempt_get_com_messages()?;
         self.msgs.append(&mut msgs);
         Ok(())
@@ -133,8 +365,9 @@ impl ComHandler {
     /// does not remove the move request from the buffer
     pub fn peak_move_request(&mut self) -> bool {
         let _ = self.try_receive_com_message();
-        self.msgs
-            .contains(&ComMessage::Room(Box::new(RoomMessage::MoveRequest)))
+        self.msgs.iter().any(
+            |msg| matches!(msg, ComMessage::Room { msg, .. } if matches!(**msg, RoomMessage::MoveRequest)),
+        )
     }
 
     /// BLOCKING: wait until `str` appears in the buffer, then remove it
@@ -144,7 +377,12 @@ impl ComHandler {
                 self.buf.drain(..pos + str.len());
                 break;
             }
-            self.try_read_new()?;
+            if self.server_closed {
+                return Err(ReceiveErr::ConnectionClosed(
+                    ConnectionClosedErr::ServerClosed,
+                ));
+            }
+            self.read_new(ReadMode::Blocking)?;
         }
         Ok(())
     }
@@ -160,8 +398,9 @@ impl ComHandler {
 
     /// NONBLOCKING: try to parse a `<comMessage>...</comMessage>` from buffer.
     fn get_com_msg_and_rm(&mut self) -> Option<ReceivedComMessage> {
-        // removing the protocol tag to avoid parsing issues
+        // removing the protocol tags to avoid parsing issues
         self.check_for_protocol_tag();
+        self.check_for_protocol_end();
 
         let prepared_buf = format!("<comMessage>{}</comMessage>", self.buf);
         let rs_msg = ReceivedComMessage::from_str(&prepared_buf);
@@ -204,37 +443,110 @@ impl ComHandler {
         };
     }
 
-    /// NONBLOCKING: try to read available bytes and append to internal buffer.
-    fn try_read_new(&mut self) -> Result<(), ReceiveErr> {
+    #[inline]
+    /// looks for the end of the protocol and removes it, together with anything after it
+    /// messages before the end stay in the buffer
+    fn check_for_protocol_end(&mut self) {
+        if let Some(pos) = self.buf.find("</protocol>") {
+            self.buf.truncate(pos);
+            self.protocol_ended = true;
+        }
+    }
+
+    /// records `chunk` and appends it to the internal buffer
+    fn push_inbound(&mut self, chunk: &str) {
+        self.writer.tap(TrafficDirection::Inbound, chunk);
+        self.buf.push_str(chunk);
+    }
+
+    /// reads available bytes into the internal buffer, blocking as `mode` allows
+    /// returns the number of bytes read, 0 if nothing arrived in time or the server closed the connection
+    fn read_new(&mut self, mode: ReadMode) -> Result<usize, ReceiveErr> {
+        self.set_read_mode(mode)?;
         let mut tmp = [0_u8; 4096];
 
         match self.reader.read(&mut tmp) {
+            Ok(0) => {
+                self.server_closed = true;
+                if !self.undecoded.is_empty() {
+                    let rest = std::mem::take(&mut self.undecoded);
+                    self.push_inbound(&String::from_utf8_lossy(&rest));
+                }
+                Ok(0)
+            }
             Ok(n) => {
-                if n == 0 {
-                    // todo better handling
-                    return Ok(());
+                let mut bytes = std::mem::take(&mut self.undecoded);
+                bytes.extend_from_slice(&tmp[..n]);
+                self.undecoded = bytes.split_off(bytes.len() - incomplete_utf8_tail(&bytes));
+                if !bytes.is_empty() {
+                    self.push_inbound(&String::from_utf8_lossy(&bytes));
                 }
-                let chunk = String::from_utf8_lossy(&tmp[..n]);
-                self.buf.push_str(&chunk);
-
-                Ok(())
+                Ok(n)
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                ) =>
+            {
+                Ok(0)
             }
-            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
             Err(e) => Err(ReceiveErr::Io(e)),
         }
     }
 
+    /// configures the socket for `mode`, only if it changed
+    fn set_read_mode(&mut self, mode: ReadMode) -> io::Result<()> {
+        if self.read_mode == Some(mode) {
+            return Ok(());
+        }
+        let socket = self.reader.get_ref();
+        match mode {
+            ReadMode::NonBlocking | ReadMode::Timeout(Duration::ZERO) => {
+                socket.set_nonblocking(true)?
+            }
+            ReadMode::Timeout(timeout) => {
+                socket.set_nonblocking(false)?;
+                socket.set_read_timeout(Some(timeout))?;
+            }
+            ReadMode::Blocking => {
+                socket.set_nonblocking(false)?;
+                socket.set_read_timeout(None)?;
+            }
+        }
+        self.read_mode = Some(mode);
+        Ok(())
+    }
+
     fn create_com_message_from_received_room(
         &self,
         received_room: ReceivedRoom,
-    ) -> Result<ComMessage, ComMessageBuildErr> {
-        let rm_msg = RoomMessage::try_from(received_room)
+    ) -> Result<ComMessage<S>, ComMessageBuildErr> {
+        let is_error = received_room
+            .data
+            .as_ref()
+            .is_some_and(|data| data.class.as_deref() == Some("error"));
+        if is_error {
+            let err = ServerError::try_from(received_room)
+                .map_err(ComMessageBuildErr::FailedBuildingServerError)?;
+            return Ok(ComMessage::Error(err));
+        }
+
+        let room_id = received_room.room_id.clone().ok_or_else(|| {
+            ComMessageBuildErr::FailedBuildingMemento("missing room id".to_string())
+        })?;
+        let rm_msg = RoomMessage::from_received_room(received_room)
             .map_err(ComMessageBuildErr::FailedBuildingMemento)?;
 
-        Ok(ComMessage::Room(Box::new(rm_msg)))
+        Ok(ComMessage::Room {
+            room_id,
+            msg: Box::new(rm_msg),
+        })
     }
 
-    fn attempt_get_com_messages(&mut self) -> Result<Vec<ComMessage>, ReceiveErr> {
+    fn attempt_get_com_messages(&mut self) -> Result<Vec<ComMessage<S>>, ReceiveErr> {
         let mut messages = Vec::new();
         if let Some(recv_com_msg) = self.get_com_msg_and_rm() {
             //todo: make code better (.___.)
@@ -275,10 +587,20 @@ impl ComHandler {
                     room_id: recv_admin_prepared.room_id.clone(),
                 })));
             }
+            if let Some(recv_admin_observed) = &recv_com_msg.admin_observed {
+                messages.push(ComMessage::Admin(AdminMessage::Observed(Observed {
+                    room_id: recv_admin_observed.room_id.clone(),
+                })));
+            }
+
+            if let Some(recv_error_packet) = recv_com_msg.error_packet {
+                messages.push(ComMessage::Error(ServerError::from(recv_error_packet)));
+            }
 
             for room in recv_com_msg.room {
-                if let Ok(msg) = self.create_com_message_from_received_room(room) {
-                    messages.push(msg);
+                match self.create_com_message_from_received_room(room) {
+                    Ok(msg) => messages.push(msg),
+                    Err(e) => info!("dropping a room message that could not be parsed: {:?}", e),
                 }
             }
         }
@@ -286,20 +608,69 @@ impl ComHandler {
     }
 
     /// Send a move. Returns `NoRoomId` if not joined yet.
-    pub fn send_move(&mut self, x: u8, y: u8, dir: Direction) -> Result<(), SendErr> {
-        if let Some(room) = &self.room_id {
-            let xml = make_move_xml(room, x.into(), y.into(), dir)
-                .map_err(|_| SendErr::FailedToBuildXml)?;
-            self.stream.write_all(xml.as_bytes())?;
-            self.stream.flush()?;
-            Ok(())
-        } else {
-            Err(SendErr::NoRoomId)
+    pub fn send_move(&mut self, mv: &S::Move) -> Result<(), SendErr> {
+        match &self.room_id {
+            Some(room) => self.writer.send_move(room, mv),
+            None => Err(SendErr::NoRoomId),
         }
     }
 
     pub fn send_raw(&mut self, xml: &str) -> Result<(), SendErr> {
-        self.stream.write_all(xml.as_bytes())?;
+        self.writer.send_raw(xml)
+    }
+
+    /// BLOCKING: leave the server gracefully
+    /// sends `<close/>` and `</protocol>`, then waits up to `DEFAULT_CLOSE_TIMEOUT`
+    /// for the server to close its side and shuts the socket down.
+    /// returns every message that was still buffered or arrived while draining
+    pub fn close(&mut self) -> Result<Vec<ComMessage<S>>, ComError> {
+        self.close_with_timeout(DEFAULT_CLOSE_TIMEOUT)
+    }
+
+    /// BLOCKING: same as `close`, but waits at most `drain_timeout` for the server
+    pub fn close_with_timeout(
+        &mut self,
+        drain_timeout: Duration,
+    ) -> Result<Vec<ComMessage<S>>, ComError> {
+        if self.writer.is_closed() {
+            return Ok(Vec::new());
+        }
+        self.writer.send_close()?;
+
+        let drain_res = self.drain(drain_timeout);
+        // the server might have closed the connection first, which is fine
+        let _ = self.reader.get_ref().shutdown(Shutdown::Both);
+        drain_res?;
+
+        Ok(std::mem::take(&mut self.msgs))
+    }
+
+    /// BLOCKING: read and parse until the server ends the protocol, closes the connection
+    /// or `drain_timeout` has passed
+    fn drain(&mut self, drain_timeout: Duration) -> Result<(), ReceiveErr> {
+        if self.protocol_ended || self.server_closed {
+            return Ok(());
+        }
+        let start_t = Instant::now();
+        loop {
+            let remaining = drain_timeout.saturating_sub(start_t.elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+            match self.read_new(ReadMode::Timeout(remaining)) {
+                // nothing arrived in time, or the server closed the connection
+                Ok(0) => break,
+                Ok(_) => {
+                    let mut msgs = self.attempt_get_com_messages()?;
+                    self.msgs.append(&mut msgs);
+                    if self.protocol_ended {
+                        break;
+                    }
+                }
+                Err(ReceiveErr::Io(e)) if e.kind() == io::ErrorKind::ConnectionReset => break,
+                Err(e) => return Err(e),
+            }
+        }
         Ok(())
     }
 
@@ -307,51 +678,235 @@ impl ComHandler {
 
     /// authenticate as admin
     pub fn send_admin_authenticate(&mut self, password: &str) -> Result<(), SendErr> {
+        self.writer.send_admin_authenticate(password)
+    }
+
+    /// observe a room
+    pub fn send_admin_observe(&mut self, room_id: &str) -> Result<(), SendErr> {
+        self.writer.send_admin_observe(room_id)
+    }
+
+    /// pause / resume a room
+    pub fn send_admin_pause(&mut self, room_id: &str, pause: bool) -> Result<(), SendErr> {
+        self.writer.send_admin_pause(room_id, pause)
+    }
+
+    /// step a paused room once
+    pub fn send_admin_step(&mut self, room_id: &str) -> Result<(), SendErr> {
+        self.writer.send_admin_step(room_id)
+    }
+
+    /// cancel a room
+    pub fn send_admin_cancel(&mut self, room_id: &str) -> Result<(), SendErr> {
+        self.writer.send_admin_cancel(room_id)
+    }
+
+    /// prepare a new room of the game type the connection was opened with
+    pub fn send_admin_prepare(
+        &mut self,
+        pause: bool,
+        slots: &[PrepareSlot],
+    ) -> Result<(), SendErr> {
+        self.writer.send_admin_prepare(pause, slots)
+    }
+}
+
+/// the write half of a `ComHandler`, can be cloned and used from any thread
+/// all clones write to the same connection, also after a reconnect
+pub struct ComSender<S: Season = Piranhas> {
+    shared: Arc<SharedWriter>,
+    /// used when preparing rooms
+    game_type: GameType,
+    season: PhantomData<fn() -> S>,
+}
+
+struct SharedWriter {
+    stream: Mutex<TcpStream>,
+    recorder: Mutex<Option<TranscriptRecorder>>,
+    /// set once `<close/>` was sent
+    closed: AtomicBool,
+}
+
+impl<S: Season> Clone for ComSender<S> {
+    fn clone(&self) -> Self {
+        ComSender {
+            shared: self.shared.clone(),
+            game_type: self.game_type.clone(),
+            season: PhantomData,
+        }
+    }
+}
+
+impl<S: Season> ComSender<S> {
+    fn new(stream: TcpStream, game_type: &GameType, recorder: Option<TranscriptRecorder>) -> Self {
+        ComSender {
+            shared: Arc::new(SharedWriter {
+                stream: Mutex::new(stream),
+                recorder: Mutex::new(recorder),
+                closed: AtomicBool::new(false),
+            }),
+            game_type: game_type.clone(),
+            season: PhantomData,
+        }
+    }
+
+    /// true once the connection was closed by `ComHandler::close`
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::SeqCst)
+    }
+
+    /// swaps in the stream of a new connection and returns the old one
+    fn replace_stream(&self, stream: TcpStream) -> TcpStream {
+        self.shared.closed.store(false, Ordering::SeqCst);
+        std::mem::replace(&mut *self.shared.stream.lock().unwrap(), stream)
+    }
+
+    fn take_recorder(&self) -> Option<TranscriptRecorder> {
+        self.shared.recorder.lock().unwrap().take()
+    }
+
+    /// writes a chunk into the transcript, if recording
+    /// recording stops if the transcript can't be written, the connection is not affected
+    fn tap(&self, direction: TrafficDirection, chunk: &str) {
+        let mut recorder = self.shared.recorder.lock().unwrap();
+        if let Some(rec) = recorder.as_mut() {
+            if let Err(e) = rec.record(direction, chunk) {
+                info!("writing the transcript failed, recording stopped: {:?}", e);
+                *recorder = None;
+            }
+        }
+    }
+
+    /// writes to the server and records the chunk
+    fn write_out(&self, data: &str) -> io::Result<()> {
+        {
+            let mut stream = self.shared.stream.lock().unwrap();
+            stream.write_all(data.as_bytes())?;
+            stream.flush()?;
+        }
+        self.tap(TrafficDirection::Outbound, data);
+        Ok(())
+    }
+
+    /// sends `<close/>` and `</protocol>`, nothing can be sent afterwards
+    /// the receiving side keeps reading until the server ends the protocol
+    pub fn send_close(&self) -> Result<(), SendErr> {
+        self.send_raw(&format!("{}</protocol>", make_close_xml()))?;
+        self.shared.closed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// shuts the socket down without saying goodbye, a blocked read on the receiving side returns
+    pub fn shutdown(&self) {
+        self.shared.closed.store(true, Ordering::SeqCst);
+        let _ = self.shared.stream.lock().unwrap().shutdown(Shutdown::Both);
+    }
+
+    /// Send a move into the room `room_id`
+    pub fn send_move(&self, room_id: &str, mv: &S::Move) -> Result<(), SendErr> {
+        let data_xml = S::make_move_data_xml(mv).map_err(|_| SendErr::FailedToBuildXml)?;
+        self.send_raw(&make_room_xml(room_id, &data_xml))
+    }
+
+    pub fn send_raw(&self, xml: &str) -> Result<(), SendErr> {
+        if self.is_closed() {
+            return Err(SendErr::ConnectionClosed);
+        }
+        self.write_out(xml)?;
+        Ok(())
+    }
+
+    /// authenticate as admin
+    pub fn send_admin_authenticate(&self, password: &str) -> Result<(), SendErr> {
         let xml = crate::outgoing::make_authenticate_xml(password)
             .map_err(|_| SendErr::FailedToBuildXml)?;
         self.send_raw(&xml)
     }
 
     /// observe a room
-    pub fn send_admin_observe(&mut self, room_id: &str) -> Result<(), SendErr> {
+    pub fn send_admin_observe(&self, room_id: &str) -> Result<(), SendErr> {
         let xml =
             crate::outgoing::make_observe_xml(room_id).map_err(|_| SendErr::FailedToBuildXml)?;
         self.send_raw(&xml)
     }
 
     /// pause / resume a room
-    pub fn send_admin_pause(&mut self, room_id: &str, pause: bool) -> Result<(), SendErr> {
+    pub fn send_admin_pause(&self, room_id: &str, pause: bool) -> Result<(), SendErr> {
         let xml = crate::outgoing::make_pause_xml(room_id, pause)
             .map_err(|_| SendErr::FailedToBuildXml)?;
         self.send_raw(&xml)
     }
 
     /// step a paused room once
-    pub fn send_admin_step(&mut self, room_id: &str) -> Result<(), SendErr> {
+    pub fn send_admin_step(&self, room_id: &str) -> Result<(), SendErr> {
         let xml = crate::outgoing::make_step_xml(room_id).map_err(|_| SendErr::FailedToBuildXml)?;
         self.send_raw(&xml)
     }
 
     /// cancel a room
-    pub fn send_admin_cancel(&mut self, room_id: &str) -> Result<(), SendErr> {
+    pub fn send_admin_cancel(&self, room_id: &str) -> Result<(), SendErr> {
         let xml =
             crate::outgoing::make_cancel_xml(room_id).map_err(|_| SendErr::FailedToBuildXml)?;
         self.send_raw(&xml)
     }
 
-    /// prepare a new room
-    /// slots is a slice of tuples: (display_name, can_timeout, reserved)
-    pub fn send_admin_prepare(
-        &mut self,
-        pause: bool,
-        slots: &[PrepareSlot],
-    ) -> Result<(), SendErr> {
-        let xml = crate::outgoing::make_prepare_xml("swc_2026_piranhas", pause, slots)
+    /// prepare a new room of the game type the connection was opened with
+    pub fn send_admin_prepare(&self, pause: bool, slots: &[PrepareSlot]) -> Result<(), SendErr> {
+        let xml = crate::outgoing::make_prepare_xml(&self.game_type.to_string(), pause, slots)
             .map_err(|_| SendErr::FailedToBuildXml)?;
         self.send_raw(&xml)
     }
 }
 
+impl<S: Season> Drop for ComHandler<S> {
+    fn drop(&mut self) {
+        if !self.writer.is_closed() {
+            let _ = self.close();
+        }
+    }
+}
+
+/// how often and how fast to retry, when connecting or reconnecting fails
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// number of retries, 0 disables reconnecting
+    pub max_attempts: u32,
+    /// wait before the first retry, doubled for every further retry
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// how long to wait for the server to confirm a rejoin
+    pub rejoin_timeout: Duration,
+}
+
+impl ReconnectPolicy {
+    pub fn disabled() -> Self {
+        ReconnectPolicy {
+            max_attempts: 0,
+            ..Default::default()
+        }
+    }
+
+    /// the wait before retry number `attempt`, starting at 0
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    /// short enough to rejoin within the time limit of a move
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_millis(500),
+            rejoin_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PrepareSlot {
     pub displayname: String,
@@ -368,3 +923,22 @@ impl PrepareSlot {
         }
     }
 }
+
+/// the number of bytes at the end of `bytes` that start a character but do not complete it
+fn incomplete_utf8_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0b1100_0000 == 0b1000_0000 {
+            // continuation byte, the character starts further back
+            continue;
+        }
+        let char_len = match byte {
+            0b1100_0000..=0b1101_1111 => 2,
+            0b1110_0000..=0b1110_1111 => 3,
+            0b1111_0000..=0b1111_0111 => 4,
+            _ => 1,
+        };
+        return if char_len > back { back } else { 0 };
+    }
+    0
+}
@@ -0,0 +1,226 @@
+//! spectator for many rooms on one admin connection
+//!
+//! every observed room gets its own timeline of game states and its result.
+//! incoming messages are turned into per room events (game started, move played, game finished)
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    admin::{AdminClient, StepOutcome},
+    error::AdminErr,
+    internal::{ComMessage, GameResult, RoomMessage},
+    season::{piranhas::Piranhas, GameType, Season},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpectatorEvent<S: Season = Piranhas> {
+    /// the first memento of a room arrived
+    GameStarted {
+        room_id: String,
+        state: Box<S::State>,
+    },
+    /// every following memento of a room
+    MovePlayed {
+        room_id: String,
+        state: Box<S::State>,
+    },
+    /// the result of a room arrived
+    GameFinished {
+        room_id: String,
+        result: Box<GameResult>,
+    },
+}
+
+impl<S: Season> SpectatorEvent<S> {
+    pub fn room_id(&self) -> &str {
+        match self {
+            SpectatorEvent::GameStarted { room_id, .. }
+            | SpectatorEvent::MovePlayed { room_id, .. }
+            | SpectatorEvent::GameFinished { room_id, .. } => room_id,
+        }
+    }
+}
+
+/// everything seen of a single room
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomTimeline<S: Season = Piranhas> {
+    pub room_id: String,
+    /// all states in the order they arrived, the first one is the start of the game
+    pub states: Vec<S::State>,
+    pub result: Option<GameResult>,
+}
+
+impl<S: Season> RoomTimeline<S> {
+    pub fn new(room_id: &str) -> Self {
+        RoomTimeline {
+            room_id: room_id.to_string(),
+            states: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// the most recent state, none before the game started
+    pub fn latest(&self) -> Option<&S::State> {
+        self.states.last()
+    }
+
+    pub fn has_started(&self) -> bool {
+        !self.states.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+}
+
+/// keeps the timelines of all rooms, without owning a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomTracker<S: Season = Piranhas> {
+    rooms: HashMap<String, RoomTimeline<S>>,
+}
+
+impl<S: Season> Default for RoomTracker<S> {
+    fn default() -> Self {
+        RoomTracker {
+            rooms: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Season> RoomTracker<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// starts an empty timeline for `room_id`, if there is none yet
+    pub fn track(&mut self, room_id: &str) -> &RoomTimeline<S> {
+        self.timeline_mut(room_id)
+    }
+
+    /// records `msg` into the timeline of its room
+    /// returns the resulting event, none for messages that do not change a timeline
+    pub fn handle(&mut self, msg: ComMessage<S>) -> Option<SpectatorEvent<S>> {
+        let ComMessage::Room { room_id, msg } = msg else {
+            return None;
+        };
+        match *msg {
+            RoomMessage::Memento(state) => Some(self.record_state(room_id, state)),
+            RoomMessage::Result(result) => Some(self.record_result(room_id, result)),
+            RoomMessage::WelcomeMessage(_) | RoomMessage::MoveRequest => None,
+        }
+    }
+
+    /// appends `state` to the timeline of `room_id`
+    pub fn record_state(&mut self, room_id: String, state: Box<S::State>) -> SpectatorEvent<S> {
+        let timeline = self.timeline_mut(&room_id);
+        let started = timeline.has_started();
+        timeline.states.push((*state).clone());
+        if started {
+            SpectatorEvent::MovePlayed { room_id, state }
+        } else {
+            SpectatorEvent::GameStarted { room_id, state }
+        }
+    }
+
+    /// stores the result of `room_id`
+    pub fn record_result(&mut self, room_id: String, result: Box<GameResult>) -> SpectatorEvent<S> {
+        self.timeline_mut(&room_id).result = Some((*result).clone());
+        SpectatorEvent::GameFinished { room_id, result }
+    }
+
+    fn timeline_mut(&mut self, room_id: &str) -> &mut RoomTimeline<S> {
+        self.rooms
+            .entry(room_id.to_string())
+            .or_insert_with(|| RoomTimeline::new(room_id))
+    }
+
+    pub fn room(&self, room_id: &str) -> Option<&RoomTimeline<S>> {
+        self.rooms.get(room_id)
+    }
+
+    pub fn rooms(&self) -> impl Iterator<Item = &RoomTimeline<S>> {
+        self.rooms.values()
+    }
+
+    /// removes a room and returns its timeline
+    pub fn forget(&mut self, room_id: &str) -> Option<RoomTimeline<S>> {
+        self.rooms.remove(room_id)
+    }
+}
+
+/// observes any number of rooms over one admin connection
+pub struct Spectator<S: Season = Piranhas> {
+    admin: AdminClient<S>,
+    tracker: RoomTracker<S>,
+}
+
+impl<S: Season> Spectator<S> {
+    /// BLOCKING: connect to `addr` and authenticate with `password`
+    pub fn connect(addr: &str, game_type: &GameType, password: &str) -> Result<Self, AdminErr> {
+        let admin = AdminClient::connect_and_authenticate(addr, game_type, password)?;
+        Ok(Self::from_admin(admin))
+    }
+
+    /// uses an already authenticated admin client
+    pub fn from_admin(admin: AdminClient<S>) -> Self {
+        Spectator {
+            admin,
+            tracker: RoomTracker::new(),
+        }
+    }
+
+    /// the underlying admin client, e.g. to prepare or step rooms
+    /// room messages received through it directly are not tracked
+    pub fn admin(&mut self) -> &mut AdminClient<S> {
+        &mut self.admin
+    }
+
+    /// BLOCKING: start observing `room_id`
+    pub fn observe(&mut self, room_id: &str) -> Result<(), AdminErr> {
+        self.admin.observe(room_id)?;
+        self.tracker.track(room_id);
+        Ok(())
+    }
+
+    /// BLOCKING: wait for the next event of any observed room
+    /// fails with `AdminErr::NoResponse`, if nothing happened within `timeout`
+    pub fn next_event(&mut self, timeout: Duration) -> Result<SpectatorEvent<S>, AdminErr> {
+        let start_t = Instant::now();
+        loop {
+            let remaining = timeout.saturating_sub(start_t.elapsed());
+            if remaining.is_zero() {
+                return Err(AdminErr::NoResponse(timeout));
+            }
+            let msg = self.admin.next_message(remaining)?;
+            if let Some(event) = self.tracker.handle(msg) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// BLOCKING: steps a paused room and tracks the outcome like any other message
+    pub fn step(&mut self, room_id: &str) -> Result<SpectatorEvent<S>, AdminErr> {
+        let event = match self.admin.step(room_id)? {
+            StepOutcome::Moved(state) => self.tracker.record_state(room_id.to_string(), state),
+            StepOutcome::GameOver(result) => {
+                self.tracker.record_result(room_id.to_string(), result)
+            }
+        };
+        Ok(event)
+    }
+
+    pub fn tracker(&self) -> &RoomTracker<S> {
+        &self.tracker
+    }
+
+    pub fn tracker_mut(&mut self) -> &mut RoomTracker<S> {
+        &mut self.tracker
+    }
+
+    pub fn room(&self, room_id: &str) -> Option<&RoomTimeline<S>> {
+        self.tracker.room(room_id)
+    }
+}
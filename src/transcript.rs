@@ -0,0 +1,287 @@
+//! recording and replaying the raw traffic of a connection
+//!
+//! a transcript is a text file with one chunk per line:
+//! `<seconds since start> <IN|OUT> <chunk>`, where `IN` is what the server sent and `OUT`
+//! what the client sent. newlines and backslashes inside a chunk are escaped
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{Shutdown, TcpListener, ToSocketAddrs},
+    path::Path,
+    str::FromStr,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::ReceiveErr,
+    internal::ComMessage,
+    season::{GameType, Season},
+    socha_com::ComHandler,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficDirection {
+    /// sent by the server
+    Inbound,
+    /// sent by the client
+    Outbound,
+}
+
+impl fmt::Display for TrafficDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrafficDirection::Inbound => write!(f, "IN"),
+            TrafficDirection::Outbound => write!(f, "OUT"),
+        }
+    }
+}
+
+impl FromStr for TrafficDirection {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "IN" => Ok(TrafficDirection::Inbound),
+            "OUT" => Ok(TrafficDirection::Outbound),
+            other => Err(format!("unknown traffic direction '{}'", other)),
+        }
+    }
+}
+
+/// a single chunk of a transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// time since the recording started
+    pub elapsed: Duration,
+    pub direction: TrafficDirection,
+    /// the exact text that was read from or written to the socket
+    pub data: String,
+}
+
+impl fmt::Display for TranscriptEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} {} {}",
+            self.elapsed.as_secs_f64(),
+            self.direction,
+            escape(&self.data)
+        )
+    }
+}
+
+impl FromStr for TranscriptEntry {
+    type Err = String;
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parts = line.splitn(3, ' ');
+        let elapsed = parts
+            .next()
+            .ok_or("missing timestamp")?
+            .parse::<f64>()
+            .map_err(|e| format!("invalid timestamp in '{}': {}", line, e))?;
+        let direction = parts
+            .next()
+            .ok_or_else(|| format!("missing direction in '{}'", line))?
+            .parse::<TrafficDirection>()?;
+        let data = unescape(parts.next().unwrap_or_default())?;
+        Ok(TranscriptEntry {
+            elapsed: Duration::from_secs_f64(elapsed),
+            direction,
+            data,
+        })
+    }
+}
+
+fn escape(data: &str) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for c in data.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(data: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(data.len());
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            other => return Err(format!("invalid escape sequence '\\{:?}'", other)),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// writes every chunk passed to `record` into a transcript file
+/// attach it with `ComHandler::join_recorded` or `ComHandler::set_recorder`
+pub struct TranscriptRecorder {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+}
+
+impl TranscriptRecorder {
+    /// creates (or truncates) the transcript file at `path`
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_writer(File::create(path)?)
+    }
+
+    pub fn from_writer<W: Write + Send + 'static>(writer: W) -> io::Result<Self> {
+        let mut writer: Box<dyn Write + Send> = Box::new(BufWriter::new(writer));
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        writeln!(
+            writer,
+            "# socha transcript, started at unix time {:.3}",
+            started_at.as_secs_f64()
+        )?;
+        writer.flush()?;
+        Ok(TranscriptRecorder {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// every chunk is flushed right away, so the transcript survives a crash of the bot
+    pub fn record(&mut self, direction: TrafficDirection, data: &str) -> io::Result<()> {
+        let entry = TranscriptEntry {
+            elapsed: self.start.elapsed(),
+            direction,
+            data: data.to_string(),
+        };
+        writeln!(self.writer, "{}", entry)?;
+        self.writer.flush()
+    }
+}
+
+/// the parsed content of a transcript file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(File::open(path)?)
+    }
+
+    pub fn read_from<R: Read>(reader: R) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = TranscriptEntry::from_str(&line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            entries.push(entry);
+        }
+        Ok(Transcript { entries })
+    }
+
+    /// everything the server sent, in order
+    pub fn inbound(&self) -> impl Iterator<Item = &TranscriptEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.direction == TrafficDirection::Inbound)
+    }
+
+    /// everything the client sent, in order
+    pub fn outbound(&self) -> impl Iterator<Item = &TranscriptEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.direction == TrafficDirection::Outbound)
+    }
+}
+
+/// a fake server that plays back the inbound side of a transcript
+pub struct Replayer {
+    transcript: Transcript,
+    /// if true, chunks are sent with the same delays as in the recording
+    pub realtime: bool,
+}
+
+impl Replayer {
+    pub fn new(transcript: Transcript, realtime: bool) -> Self {
+        Replayer {
+            transcript,
+            realtime,
+        }
+    }
+
+    /// binds to `addr` and replays the transcript to the first client that connects
+    /// returns the address the fake server listens on (useful with port 0) and a handle,
+    /// which resolves to everything the client sent
+    pub fn spawn<A: ToSocketAddrs>(
+        self,
+        addr: A,
+    ) -> io::Result<(String, JoinHandle<io::Result<String>>)> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?.to_string();
+        let handle = thread::spawn(move || self.serve(listener));
+        Ok((local_addr, handle))
+    }
+
+    /// BLOCKING: accepts one client on `listener` and replays the transcript to it
+    /// after the last chunk the server stops writing, but keeps reading until the client closes
+    pub fn serve(self, listener: TcpListener) -> io::Result<String> {
+        let (mut stream, _) = listener.accept()?;
+        let mut client_reader = stream.try_clone()?;
+        let client_handle = thread::spawn(move || {
+            let mut sent = Vec::new();
+            let _ = client_reader.read_to_end(&mut sent);
+            String::from_utf8_lossy(&sent).into_owned()
+        });
+
+        let start = Instant::now();
+        for entry in self.transcript.inbound() {
+            if self.realtime {
+                thread::sleep(entry.elapsed.saturating_sub(start.elapsed()));
+            }
+            stream.write_all(entry.data.as_bytes())?;
+            stream.flush()?;
+        }
+        // like the real server after a game, but still reading what the client sends
+        stream.shutdown(Shutdown::Write)?;
+
+        let sent = client_handle
+            .join()
+            .map_err(|_| io::Error::other("client reader panicked"))?;
+        Ok(sent)
+    }
+}
+
+/// BLOCKING: feeds the inbound side of `transcript` through the parser of `ComHandler`
+/// and returns every message, in the order the client would have received them
+pub fn replay_messages<S: Season>(
+    transcript: Transcript,
+    game_type: &GameType,
+) -> Result<Vec<ComMessage<S>>, ReceiveErr> {
+    let (addr, server_handle) = Replayer::new(transcript, false).spawn("127.0.0.1:0")?;
+    let mut com = ComHandler::<S>::connect_to_server_with_game_type(&addr, game_type)?;
+
+    let mut msgs = Vec::new();
+    loop {
+        match com.wait_for_com_message(Duration::from_secs(5)) {
+            Ok(msg) => msgs.push(msg),
+            Err(ReceiveErr::ConnectionClosed(_)) => break,
+            Err(e) => return Err(e),
+        }
+    }
+    drop(com);
+    let _ = server_handle.join();
+    Ok(msgs)
+}
@@ -0,0 +1,111 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use socha::{
+        admin::{Ack, AdminClient, StepOutcome},
+        error::AdminErr,
+        internal::ServerErrorReason,
+        season::{piranhas::Piranhas, Season},
+        socha_com::PrepareSlot,
+    };
+
+    use crate::common::memento_xml;
+
+    /// answers admin requests like the server would, rejecting every password but `secret`
+    fn spawn_admin_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = String::new();
+            let mut tmp = [0_u8; 1024];
+            stream.write_all(b"<protocol>").unwrap();
+            loop {
+                let n = match stream.read(&mut tmp) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                received.push_str(&String::from_utf8_lossy(&tmp[..n]));
+                while let Some(end) = received.find("/>") {
+                    let request: String = received.drain(..end + 2).collect();
+                    let response = if request.contains("<authenticate") {
+                        if request.contains("password=\"secret\"") {
+                            String::new()
+                        } else {
+                            "<errorpacket message=\"wrong password\"><originalRequest class=\"authenticate\"/></errorpacket>".to_string()
+                        }
+                    } else if request.contains("<slot") {
+                        // the second slot closes the prepare request
+                        if !request.contains("Player_2") {
+                            continue;
+                        }
+                        received.drain(..received.find("</prepare>").unwrap() + 10);
+                        "<prepared roomId=\"room-1\"><reservation>res-1</reservation><reservation>res-2</reservation></prepared>".to_string()
+                    } else if request.contains("<observe") {
+                        "<observed roomId=\"room-1\"/>".to_string()
+                    } else if request.contains("<step") {
+                        memento_xml("room-1", 1)
+                    } else {
+                        String::new()
+                    };
+                    stream.write_all(response.as_bytes()).unwrap();
+                }
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn test_admin_requests_return_typed_responses() {
+        let addr = spawn_admin_server();
+        let mut admin: AdminClient =
+            AdminClient::connect_and_authenticate(&addr, &Piranhas::game_type(), "secret").unwrap();
+
+        let slots = [
+            PrepareSlot::new("Player_1".to_string(), true, true),
+            PrepareSlot::new("Player_2".to_string(), true, true),
+        ];
+        let prepared = admin.prepare(true, &slots).unwrap();
+        assert_eq!(prepared.room_id, "room-1");
+        assert_eq!(
+            prepared.reservations,
+            ("res-1".to_string(), "res-2".to_string())
+        );
+
+        let observed = admin.observe("room-1").unwrap();
+        assert_eq!(observed.room_id, "room-1");
+
+        match admin.step("room-1").unwrap() {
+            StepOutcome::Moved(state) => assert_eq!(state.turn, 1),
+            other => panic!("expected a memento, got {:?}", other),
+        }
+
+        let paused = admin.pause("room-1", false).unwrap();
+        assert!(!paused.paused);
+        assert_eq!(paused.ack, Ack::Unconfirmed);
+    }
+
+    #[test]
+    fn test_admin_authenticate_reports_wrong_password() {
+        let addr = spawn_admin_server();
+        let res = AdminClient::<Piranhas>::connect_and_authenticate(
+            &addr,
+            &Piranhas::game_type(),
+            "wrong",
+        );
+        match res {
+            Err(AdminErr::Server(err)) => {
+                assert_eq!(err.reason, ServerErrorReason::AuthenticationFailed)
+            }
+            Err(e) => panic!("expected a server error, got {:?}", e),
+            Ok(_) => panic!("expected a server error, got a client"),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    use socha::{
+        error::AdminErr,
+        i_admin_handler::{
+            handler_trait::IAdminHandler, start_admin, AdminCommandSender, AdminConfig,
+        },
+        i_client_handler::{
+            handler_trait::HandlerControl, start_iclient_with_config, ClientConfig,
+        },
+        internal::{GameResult, GameState, PreparedRoom, ServerError, ServerErrorReason},
+        mock_server::MockServerConfig,
+        socha_com::PrepareSlot,
+    };
+
+    use crate::common::{start_mock, FirstMoveBot};
+
+    /// prepares a room, observes it, seats two bots once observing and leaves after the result
+    struct Manager {
+        addr: String,
+        commands: Option<AdminCommandSender>,
+        authenticated: bool,
+        prepared: Option<PreparedRoom>,
+        turns: Vec<u32>,
+        results: u32,
+        server_errors: Vec<ServerErrorReason>,
+        players: Vec<JoinHandle<()>>,
+    }
+
+    impl Manager {
+        fn new(addr: &str) -> Self {
+            Manager {
+                addr: addr.to_string(),
+                commands: None,
+                authenticated: false,
+                prepared: None,
+                turns: Vec::new(),
+                results: 0,
+                server_errors: Vec::new(),
+                players: Vec::new(),
+            }
+        }
+    }
+
+    impl IAdminHandler for Manager {
+        fn on_authenticated(&mut self, commands: AdminCommandSender) -> HandlerControl {
+            self.authenticated = true;
+            // answered with an error, which must not count as a rejected password
+            commands.observe("no-such-room").unwrap();
+            let slots = [
+                PrepareSlot::new("one".to_string(), true, true),
+                PrepareSlot::new("two".to_string(), true, true),
+            ];
+            commands.prepare(false, &slots).unwrap();
+            self.commands = Some(commands);
+            HandlerControl::Continue
+        }
+
+        fn on_prepared(&mut self, prepared: &PreparedRoom) -> HandlerControl {
+            self.commands
+                .as_ref()
+                .unwrap()
+                .observe(&prepared.room_id)
+                .unwrap();
+            self.prepared = Some(prepared.clone());
+            HandlerControl::Continue
+        }
+
+        fn on_observed(&mut self, room_id: &str) -> HandlerControl {
+            let prepared = self.prepared.as_ref().unwrap();
+            assert_eq!(room_id, prepared.room_id);
+            let (one, two) = prepared.reservations.clone();
+            for reservation in [one, two] {
+                let config = ClientConfig {
+                    addr: self.addr.clone(),
+                    reservation_code: Some(reservation),
+                    ..Default::default()
+                };
+                self.players.push(thread::spawn(move || {
+                    start_iclient_with_config(&config, &mut FirstMoveBot).unwrap();
+                }));
+            }
+            HandlerControl::Continue
+        }
+
+        fn on_room_state(&mut self, _room_id: &str, state: &GameState) -> HandlerControl {
+            self.turns.push(state.turn);
+            HandlerControl::Continue
+        }
+
+        fn on_server_error(&mut self, err: &ServerError) -> HandlerControl {
+            self.server_errors.push(err.reason);
+            HandlerControl::Continue
+        }
+
+        fn on_room_result(&mut self, _room_id: &str, _result: &GameResult) -> HandlerControl {
+            self.results += 1;
+            HandlerControl::Leave
+        }
+    }
+
+    #[test]
+    fn test_admin_handler_runs_a_prepared_room() {
+        let mock = start_mock(4, Duration::from_millis(300));
+        let config = AdminConfig {
+            addr: mock.addr().to_string(),
+            password: MockServerConfig::default().password,
+            ..Default::default()
+        };
+        let mut manager = Manager::new(mock.addr());
+        let summary = start_admin(&config, &mut manager).unwrap();
+        for player in manager.players.drain(..) {
+            player.join().unwrap();
+        }
+
+        assert!(manager.authenticated);
+        assert_eq!(manager.turns, vec![0, 1, 2, 3, 4]);
+        assert_eq!(manager.results, 1);
+        assert_eq!(manager.server_errors, vec![ServerErrorReason::UnknownRoom]);
+        assert_eq!(summary.prepared.len(), 1);
+        assert_eq!(summary.results.len(), 1);
+        assert_eq!(summary.results[0].0, summary.prepared[0].room_id);
+    }
+
+    #[test]
+    fn test_admin_handler_reports_wrong_password() {
+        let mock = start_mock(4, Duration::from_millis(300));
+        let config = AdminConfig {
+            addr: mock.addr().to_string(),
+            password: "wrong".to_string(),
+            ..Default::default()
+        };
+        let mut manager = Manager::new(mock.addr());
+        match start_admin(&config, &mut manager) {
+            Err(AdminErr::Server(err)) => {
+                assert_eq!(err.reason, ServerErrorReason::AuthenticationFailed)
+            }
+            other => panic!("expected a server error, got {:?}", other),
+        }
+        assert!(!manager.authenticated);
+    }
+}
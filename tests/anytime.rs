@@ -0,0 +1,112 @@
+#[cfg(test)]
+pub mod tests {
+    use std::{thread, time::Duration};
+
+    use socha::{
+        admin::AdminClient,
+        error::HandlerErr,
+        i_client_handler::{
+            handler_trait::{HandlerControl, IClientHandler},
+            middleware::Middleware,
+            move_context::MoveContext,
+            start_iclient_anytime, ClientConfig,
+        },
+        internal::GameState,
+        mock_server::{MockServer, MockServerConfig},
+        neutral::Move,
+        season::{piranhas::Piranhas, Season},
+        socha_com::PrepareSlot,
+    };
+
+    const MOVE_TIME_LIMIT: Duration = Duration::from_millis(500);
+
+    /// never returns before the framework sent a move, optionally publishes a legal move first
+    struct SlowBot {
+        publish: bool,
+        moves_requested: u32,
+    }
+
+    impl IClientHandler for SlowBot {
+        fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+            self.moves_requested += 1;
+            let moves = ctx.state.possible_moves();
+            if self.publish {
+                ctx.best.publish(*moves.last().unwrap());
+            }
+            while !ctx.best.should_stop() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            // too late, must not be sent
+            Ok(moves[0])
+        }
+
+        fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+            HandlerControl::Continue
+        }
+    }
+
+    #[test]
+    fn test_anytime_sends_best_move_or_fallback_before_deadline() {
+        let mock = MockServer::spawn(
+            "127.0.0.1:0",
+            MockServerConfig {
+                max_turns: 8,
+                move_timeout: MOVE_TIME_LIMIT,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut admin: AdminClient = AdminClient::connect_and_authenticate(
+            mock.addr(),
+            &Piranhas::game_type(),
+            &MockServerConfig::default().password,
+        )
+        .unwrap();
+        let slots = [
+            PrepareSlot::new("publishing".to_string(), true, true),
+            PrepareSlot::new("silent".to_string(), true, true),
+        ];
+        let prepared = admin.prepare(false, &slots).unwrap();
+
+        let players: Vec<_> = [
+            (prepared.reservations.0, true),
+            (prepared.reservations.1, false),
+        ]
+        .into_iter()
+        .map(|(reservation, publish)| {
+            let config = ClientConfig {
+                addr: mock.addr().to_string(),
+                reservation_code: Some(reservation),
+                move_time_limit: MOVE_TIME_LIMIT,
+                ..Default::default()
+            };
+            let mut bot = SlowBot {
+                publish,
+                moves_requested: 0,
+            };
+            thread::spawn(move || {
+                let summary = start_iclient_anytime(
+                    &config,
+                    &mut bot,
+                    Duration::from_millis(150),
+                    Middleware::new(),
+                )
+                .unwrap();
+                (summary, bot.moves_requested)
+            })
+        })
+        .collect();
+
+        for handle in players {
+            let (summary, moves_requested) = handle.join().unwrap();
+            assert_eq!(moves_requested, 4);
+            assert_eq!(summary.moves_sent, 4);
+            let result = summary.result.expect("no result received");
+            assert!(
+                result.winner.as_ref().is_none_or(|w| w.regular),
+                "{:?}",
+                result.winner
+            );
+        }
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+pub mod tests {
+    use std::path::PathBuf;
+
+    use log::LevelFilter;
+    use socha::{error::ArgsErr, i_client_handler::args::ClientArgs, season::GameType};
+
+    #[test]
+    fn test_parse_contest_arguments() {
+        let args = ClientArgs::parse([
+            "--host",
+            "gameserver",
+            "--port",
+            "13051",
+            "--reservation",
+            "abc-123",
+        ])
+        .unwrap();
+        assert_eq!(args.addr(), "gameserver:13051");
+        assert_eq!(args.reservation.as_deref(), Some("abc-123"));
+        assert!(args.unknown.is_empty());
+
+        let config = args.to_config();
+        assert_eq!(config.addr, "gameserver:13051");
+        assert_eq!(config.reservation_code.as_deref(), Some("abc-123"));
+    }
+
+    #[test]
+    fn test_parse_short_and_inline_arguments() {
+        let args = ClientArgs::parse([
+            "-h",
+            "127.0.0.1",
+            "--port=13052",
+            "-r=x=y",
+            "--log-file",
+            "bot.log",
+            "--log-level=debug",
+            "--verbose",
+        ])
+        .unwrap();
+        assert_eq!(args.addr(), "127.0.0.1:13052");
+        assert_eq!(args.reservation.as_deref(), Some("x=y"));
+        assert_eq!(args.log_file, Some(PathBuf::from("bot.log")));
+        assert_eq!(args.log_level, LevelFilter::Debug);
+        assert_eq!(args.unknown, vec!["--verbose".to_string()]);
+    }
+
+    #[test]
+    fn test_ipv6_host_and_game_type() {
+        let args =
+            ClientArgs::parse(["--host", "::1", "-p", "13050", "-g", "swc_2027_piranhas"]).unwrap();
+        assert_eq!(args.addr(), "[::1]:13050");
+        assert_eq!(args.game_type, GameType::new(2027, "piranhas"));
+        assert_eq!(args.to_config().game_type, GameType::new(2027, "piranhas"));
+
+        let args = ClientArgs::parse(["--host", "[::1]"]).unwrap();
+        assert_eq!(args.addr(), "[::1]:13050");
+        assert_eq!(
+            ClientArgs::parse(["--game-type", "piranhas"]),
+            Err(ArgsErr::InvalidGameType("piranhas".to_string()))
+        );
+        assert_eq!(
+            ClientArgs::parse(["-g", "swc_2025_hase"]),
+            Err(ArgsErr::UnsupportedGameType("swc_2025_hase".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_defaults_and_errors() {
+        let args = ClientArgs::parse(Vec::<String>::new()).unwrap();
+        assert_eq!(args, ClientArgs::default());
+        assert_eq!(args.addr(), "localhost:13050");
+
+        assert_eq!(
+            ClientArgs::parse(["--port", "abc"]),
+            Err(ArgsErr::InvalidPort("abc".to_string()))
+        );
+        assert_eq!(
+            ClientArgs::parse(["--host", "a", "--reservation"]),
+            Err(ArgsErr::MissingValue("--reservation".to_string()))
+        );
+        assert_eq!(ClientArgs::parse(["--no-log"]).unwrap().log_file, None);
+    }
+}
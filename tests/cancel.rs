@@ -0,0 +1,44 @@
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use socha::i_client_handler::cancel::{CancelReason, CancelToken};
+
+    #[test]
+    fn test_first_reason_wins_and_clones_share_it() {
+        let token = CancelToken::new();
+        let search = token.clone();
+        assert!(!search.is_cancelled());
+        assert_eq!(token.wait_timeout(Duration::from_millis(10)), None);
+
+        let waiter = thread::spawn(move || search.wait());
+        thread::sleep(Duration::from_millis(20));
+        assert!(token.cancel(CancelReason::MoveRequest));
+        assert!(!token.cancel(CancelReason::RoomLeft));
+        assert_eq!(waiter.join().unwrap(), CancelReason::MoveRequest);
+        assert_eq!(token.reason(), Some(CancelReason::MoveRequest));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let token = CancelToken::new();
+        token.cancel_after(Duration::from_millis(50));
+        assert!(!token.is_cancelled());
+
+        let start = Instant::now();
+        // waiting longer than the timeout wakes up at the timeout
+        assert_eq!(
+            token.wait_timeout(Duration::from_secs(5)),
+            Some(CancelReason::Timeout)
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(!token.cancel(CancelReason::Disconnected));
+
+        let token = CancelToken::new();
+        token.cancel_after(Duration::from_millis(20));
+        assert_eq!(token.wait(), CancelReason::Timeout);
+    }
+}
@@ -0,0 +1,302 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    };
+
+    use socha::{
+        admin::AdminClient,
+        error::{ClientErr, HandlerErr},
+        i_client_handler::{
+            cancel::{CancelReason, CancelToken},
+            handler_trait::{HandlerControl, IClientHandler},
+            move_context::MoveContext,
+            start_iclient_with_config, ClientConfig, ClientEnd, ClientSummary, MoveRecovery,
+            RecoveryPolicy,
+        },
+        internal::{GameResult, GameState},
+        mock_server::{MockServer, MockServerConfig},
+        neutral::Move,
+        season::{piranhas::Piranhas, Season},
+        socha_com::PrepareSlot,
+    };
+
+    use crate::common::start_mock;
+
+    /// plays the first legal move, returns `on_first_state` for the first memento
+    struct RecordingBot {
+        on_first_state: HandlerControl,
+        states: u32,
+        results: u32,
+        opponent_moves: Vec<Move>,
+        wait_reasons: Vec<Option<CancelReason>>,
+    }
+
+    impl RecordingBot {
+        fn new(on_first_state: HandlerControl) -> Self {
+            RecordingBot {
+                on_first_state,
+                states: 0,
+                results: 0,
+                opponent_moves: Vec::new(),
+                wait_reasons: Vec::new(),
+            }
+        }
+    }
+
+    impl IClientHandler for RecordingBot {
+        fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+            Ok(ctx.state.possible_moves()[0])
+        }
+
+        fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+            self.states += 1;
+            if self.states == 1 {
+                self.on_first_state
+            } else {
+                HandlerControl::Continue
+            }
+        }
+
+        fn on_game_result(&mut self, _res: &GameResult) -> HandlerControl {
+            self.results += 1;
+            HandlerControl::Continue
+        }
+
+        fn on_opponent_move(
+            &mut self,
+            mv: Move,
+            before: &GameState,
+            after: &GameState,
+        ) -> HandlerControl {
+            assert_eq!(before.turn + 1, after.turn);
+            assert_eq!(after.last_move, Some(mv));
+            self.opponent_moves.push(mv);
+            HandlerControl::Continue
+        }
+
+        fn while_waiting(&mut self, _state: &GameState, cancel: CancelToken) {
+            self.wait_reasons
+                .push(cancel.wait_timeout(Duration::from_secs(5)));
+        }
+    }
+
+    /// fails its first move request, either with an error or a panic
+    struct FailingBot {
+        panic: bool,
+        requests: u32,
+        errors: Vec<ClientErr<'static>>,
+    }
+
+    impl FailingBot {
+        fn new(panic: bool) -> Self {
+            FailingBot {
+                panic,
+                requests: 0,
+                errors: Vec::new(),
+            }
+        }
+    }
+
+    impl IClientHandler for FailingBot {
+        fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+            self.requests += 1;
+            if self.requests == 1 {
+                if self.panic {
+                    panic!("search exploded");
+                }
+                return Err("no idea".into());
+            }
+            Ok(ctx.state.possible_moves()[0])
+        }
+
+        fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+            HandlerControl::Continue
+        }
+
+        fn on_error(&mut self, err: &ClientErr) -> HandlerControl {
+            self.errors.push(match err {
+                ClientErr::CalculateMove(e) => ClientErr::CalculateMove(e.to_string().into()),
+                ClientErr::CalculateMovePanicked(msg) => {
+                    ClientErr::CalculateMovePanicked(msg.clone())
+                }
+                other => panic!("unexpected error {:?}", other),
+            });
+            HandlerControl::Continue
+        }
+    }
+
+    /// runs `bot` with `reservation` in its own thread
+    fn spawn_client<I>(
+        mock: &MockServer,
+        reservation: String,
+        recovery: RecoveryPolicy,
+        mut bot: I,
+    ) -> JoinHandle<(ClientSummary, I)>
+    where
+        I: IClientHandler + Send + 'static,
+    {
+        let config = ClientConfig {
+            addr: mock.addr().to_string(),
+            reservation_code: Some(reservation),
+            recovery,
+            ..Default::default()
+        };
+        thread::spawn(move || {
+            let summary = start_iclient_with_config(&config, &mut bot).unwrap();
+            (summary, bot)
+        })
+    }
+
+    /// prepares a room and returns both reservation codes
+    fn prepare(mock: &MockServer) -> (String, String) {
+        let mut admin: AdminClient = AdminClient::connect_and_authenticate(
+            mock.addr(),
+            &Piranhas::game_type(),
+            &MockServerConfig::default().password,
+        )
+        .unwrap();
+        let slots = [
+            PrepareSlot::new("one".to_string(), true, true),
+            PrepareSlot::new("two".to_string(), true, true),
+        ];
+        admin.prepare(false, &slots).unwrap().reservations
+    }
+
+    /// prepares a room and plays it with `one` and `two` in their own threads
+    fn play(
+        mock: &MockServer,
+        one: RecordingBot,
+        two: RecordingBot,
+    ) -> Vec<(ClientSummary, RecordingBot)> {
+        let reservations = prepare(mock);
+        let handles = [
+            spawn_client(mock, reservations.0, RecoveryPolicy::default(), one),
+            spawn_client(mock, reservations.1, RecoveryPolicy::default(), two),
+        ];
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_several_games_in_one_process() {
+        let mock = start_mock(4, Duration::from_millis(300));
+        for _ in 0..2 {
+            let players = play(
+                &mock,
+                RecordingBot::new(HandlerControl::Continue),
+                RecordingBot::new(HandlerControl::Continue),
+            );
+            for (summary, bot) in players {
+                assert_eq!(summary.end, ClientEnd::ServerEnded);
+                assert_eq!(bot.results, 1);
+                assert_eq!(summary.moves_sent, 2);
+                assert_eq!(summary.last_state.turn, 4);
+                assert_eq!(summary.desyncs, 0);
+                assert_eq!(bot.opponent_moves.len(), 2);
+                // result and left can arrive in one read, left is parsed first then
+                assert!(
+                    matches!(
+                        bot.wait_reasons.as_slice(),
+                        [
+                            Some(CancelReason::MoveRequest),
+                            Some(CancelReason::GameOver | CancelReason::RoomLeft)
+                        ]
+                    ),
+                    "{:?}",
+                    bot.wait_reasons
+                );
+                assert!(summary.room_id.is_some());
+                assert!(summary.team.is_some());
+                let result = summary.result.expect("no result in the summary");
+                assert!(result.winner.is_none_or(|w| w.regular));
+            }
+        }
+    }
+
+    #[test]
+    fn test_leave_and_stop() {
+        let mock = start_mock(4, Duration::from_millis(300));
+        let start = Instant::now();
+        let players = play(
+            &mock,
+            RecordingBot::new(HandlerControl::Leave),
+            RecordingBot::new(HandlerControl::Stop),
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let (left, left_bot) = &players[0];
+        assert_eq!(left.end, ClientEnd::HandlerLeft);
+        assert_eq!(left_bot.states, 1);
+        assert_eq!(left.moves_sent, 0);
+
+        let (stopped, stopped_bot) = &players[1];
+        assert_eq!(stopped.end, ClientEnd::HandlerStopped);
+        assert_eq!(stopped_bot.states, 1);
+        assert_eq!(stopped.moves_sent, 0);
+        assert!(stopped.result.is_none());
+    }
+
+    #[test]
+    fn test_failed_moves_are_replaced_by_fallback() {
+        let mock = start_mock(4, Duration::from_millis(300));
+        let reservations = prepare(&mock);
+        let one = spawn_client(
+            &mock,
+            reservations.0,
+            RecoveryPolicy::default(),
+            FailingBot::new(false),
+        );
+        let two = spawn_client(
+            &mock,
+            reservations.1,
+            RecoveryPolicy::default(),
+            FailingBot::new(true),
+        );
+        for (handle, panicked) in [(one, false), (two, true)] {
+            let (summary, bot) = handle.join().unwrap();
+            assert_eq!(summary.end, ClientEnd::ServerEnded);
+            assert_eq!(summary.moves_sent, 2);
+            let result = summary.result.expect("no result in the summary");
+            assert!(result.winner.is_none_or(|w| w.regular));
+            assert_eq!(bot.errors.len(), 1);
+            match &bot.errors[0] {
+                ClientErr::CalculateMovePanicked(msg) if panicked => {
+                    assert_eq!(msg, "search exploded")
+                }
+                ClientErr::CalculateMove(e) if !panicked => assert_eq!(e.to_string(), "no idea"),
+                other => panic!("unexpected error {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_failed_move_leaves_with_leave_policy() {
+        let mock = start_mock(4, Duration::from_millis(300));
+        let reservations = prepare(&mock);
+        let leave = RecoveryPolicy {
+            move_failed: MoveRecovery::Leave,
+            ..Default::default()
+        };
+        let one = spawn_client(&mock, reservations.0, leave, FailingBot::new(false));
+        let two = spawn_client(
+            &mock,
+            reservations.1,
+            RecoveryPolicy::default(),
+            RecordingBot::new(HandlerControl::Continue),
+        );
+
+        let (summary, bot) = one.join().unwrap();
+        assert_eq!(summary.end, ClientEnd::HandlerLeft);
+        assert_eq!(summary.moves_sent, 0);
+        assert_eq!(bot.errors.len(), 1);
+
+        // the opponent wins, because no move was sent
+        let (summary, _) = two.join().unwrap();
+        let winner = summary.result.unwrap().winner.unwrap();
+        assert_eq!(Some(winner.team), summary.team);
+        assert!(!winner.regular);
+    }
+}
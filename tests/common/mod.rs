@@ -0,0 +1,118 @@
+//! fixtures shared by the integration tests, every test crate uses only some of them
+#![allow(dead_code)]
+
+use std::{path::PathBuf, process::Command};
+
+#[cfg(feature = "tools")]
+use socha::mock_server::{MockServer, MockServerConfig};
+use socha::{
+    error::HandlerErr,
+    i_client_handler::{
+        handler_trait::{HandlerControl, IClientHandler},
+        move_context::MoveContext,
+    },
+    incoming::ReceivedComMessage,
+    internal::{GameState, RoomMessage},
+    neutral::Move,
+};
+use strong_xml::XmlRead;
+
+/// the start board of a piranhas game, as sent by the server and the mock server
+const START_BOARD: &str = "
+    EMPTY TWO_M TWO_S TWO_L TWO_S TWO_L TWO_L TWO_M TWO_S EMPTY
+    ONE_M EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY ONE_S
+    ONE_S EMPTY EMPTY EMPTY EMPTY EMPTY SQUID EMPTY EMPTY ONE_S
+    ONE_L EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY ONE_L
+    ONE_S EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY ONE_M
+    ONE_L EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY ONE_M
+    ONE_L EMPTY EMPTY SQUID EMPTY EMPTY EMPTY EMPTY EMPTY ONE_S
+    ONE_M EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY ONE_S
+    ONE_S EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY EMPTY ONE_L
+    EMPTY TWO_S TWO_S TWO_L TWO_M TWO_M TWO_S TWO_S TWO_L EMPTY
+";
+
+/// always plays the first legal move
+pub struct FirstMoveBot;
+
+impl IClientHandler for FirstMoveBot {
+    fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+        Ok(ctx.state.possible_moves()[0])
+    }
+
+    fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+        HandlerControl::Continue
+    }
+}
+
+/// a mock server on a free local port, with the default password and start state
+#[cfg(feature = "tools")]
+pub fn start_mock(max_turns: u32, move_timeout: std::time::Duration) -> MockServer {
+    MockServer::spawn(
+        "127.0.0.1:0",
+        MockServerConfig {
+            max_turns,
+            move_timeout,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+/// builds `examples/i_client_handler_example.rs` and returns the path of the executable
+pub fn example_bot() -> PathBuf {
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--example", "i_client_handler_example"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    // the test executable lives in target/<profile>/deps
+    let profile_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    profile_dir.join("examples").join(format!(
+        "i_client_handler_example{}",
+        std::env::consts::EXE_SUFFIX
+    ))
+}
+
+/// a memento of `room_id` with an empty board
+pub fn memento_xml(room_id: &str, turn: u32) -> String {
+    let empty_row = "EMPTY ".repeat(10);
+    board_memento_xml(room_id, turn, &format!("{}\n", empty_row).repeat(10))
+}
+
+/// the state at the start of a game, parsed from a memento
+pub fn start_state() -> GameState {
+    let xml = format!(
+        "<comMessage>{}</comMessage>",
+        board_memento_xml("room", 0, START_BOARD)
+    );
+    let mut com_message = ReceivedComMessage::from_str(&xml).unwrap();
+    match RoomMessage::try_from(com_message.room.remove(0)).unwrap() {
+        RoomMessage::Memento(state) => *state,
+        other => panic!("expected a memento, got {:?}", other),
+    }
+}
+
+/// a memento of `room_id` with `board`, one line of field names per row
+fn board_memento_xml(room_id: &str, turn: u32, board: &str) -> String {
+    let rows: String = board
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: String = line
+                .split_whitespace()
+                .map(|field| format!("<field>{}</field>", field))
+                .collect();
+            format!("<row>{}</row>", fields)
+        })
+        .collect();
+    format!(
+        "<room roomId=\"{}\"><data class=\"memento\"><state class=\"state\" startTeam=\"ONE\" turn=\"{}\"><board>{}</board></state></data></room>",
+        room_id, turn, rows
+    )
+}
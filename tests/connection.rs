@@ -0,0 +1,182 @@
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    use socha::{
+        error::{ConnectionClosedErr, ReceiveErr, RejoinErr},
+        internal::{ComMessage, Joined, ServerErrorReason},
+        season::{piranhas::Piranhas, Season},
+        socha_com::{ComHandler, ReconnectPolicy},
+        transcript::{Transcript, TranscriptRecorder},
+    };
+
+    /// accepts a connection and reads until `expected` was received
+    fn accept_and_read_until(listener: &TcpListener, expected: &str) -> std::net::TcpStream {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        let mut tmp = [0_u8; 1024];
+        while !received.contains(expected) {
+            let n = stream.read(&mut tmp).unwrap();
+            assert_ne!(n, 0, "client closed before sending {}", expected);
+            received.push_str(&String::from_utf8_lossy(&tmp[..n]));
+        }
+        stream
+    }
+
+    /// joins with reservation code `abc`, then the server drops the first connection
+    /// and answers the rejoin with `rejoin_response`
+    fn join_drop_and_reconnect(rejoin_response: &'static str) -> Result<(), ReceiveErr> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let mut first = accept_and_read_until(&listener, "reservationCode=\"abc\"");
+            first
+                .write_all(b"<protocol><joined roomId=\"room-1\"/>")
+                .unwrap();
+            thread::sleep(Duration::from_millis(50));
+            drop(first);
+
+            let mut second = accept_and_read_until(&listener, "reservationCode=\"abc\"");
+            second.write_all(rejoin_response.as_bytes()).unwrap();
+            thread::sleep(Duration::from_millis(100));
+        });
+
+        let mut com: ComHandler = ComHandler::join(&addr, Some("abc")).unwrap();
+        let joined = com.wait_for_com_message(Duration::from_secs(5)).unwrap();
+        assert!(matches!(joined, ComMessage::Joined(_)));
+        let dropped = com.wait_for_com_message(Duration::from_secs(5));
+        assert!(matches!(
+            dropped,
+            Err(ReceiveErr::ConnectionClosed(
+                ConnectionClosedErr::ServerClosed
+            ))
+        ));
+
+        let res = com.reconnect(&ReconnectPolicy::default());
+        server.join().unwrap();
+        res
+    }
+
+    #[test]
+    fn test_reconnect_rejoins_with_reservation_code() {
+        join_drop_and_reconnect("<protocol><joined roomId=\"room-1\"/>").unwrap();
+    }
+
+    #[test]
+    fn test_reconnect_reports_rejected_rejoin() {
+        let res = join_drop_and_reconnect(
+            "<protocol><errorpacket message=\"Unknown reservation code abc\">\
+             <originalRequest class=\"joinPrepared\" reservationCode=\"abc\"/></errorpacket>",
+        );
+        match res {
+            Err(ReceiveErr::RejoinFailed(RejoinErr::Rejected(err))) => {
+                assert_eq!(err.reason, ServerErrorReason::UnknownReservationCode);
+            }
+            other => panic!("expected a rejected rejoin, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reconnect_drops_split_character_of_old_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let mut first = accept_and_read_until(&listener, "reservationCode=\"abc\"");
+            first
+                .write_all(b"<protocol><joined roomId=\"room-1\"/>")
+                .unwrap();
+            thread::sleep(Duration::from_millis(50));
+            // the connection hangs in the middle of an ä
+            first.write_all(b"<room roomId=\"r\xc3").unwrap();
+
+            let mut second = accept_and_read_until(&listener, "reservationCode=\"abc\"");
+            drop(first);
+            second
+                .write_all(b"<protocol><joined roomId=\"room-1\"/>")
+                .unwrap();
+            thread::sleep(Duration::from_millis(100));
+        });
+
+        let path = std::env::temp_dir().join(format!(
+            "socha_reconnect_split_test_{}.log",
+            std::process::id()
+        ));
+        let recorder = TranscriptRecorder::create(&path).unwrap();
+        let mut com: ComHandler =
+            ComHandler::join_recorded(&addr, Some("abc"), &Piranhas::game_type(), recorder)
+                .unwrap();
+        let joined = com.wait_for_com_message(Duration::from_secs(5)).unwrap();
+        assert!(matches!(joined, ComMessage::Joined(_)));
+        let hanging = com.wait_for_com_message(Duration::from_millis(300));
+        assert!(matches!(
+            hanging,
+            Err(ReceiveErr::ConnectionClosed(
+                ConnectionClosedErr::NoMessageReceivedFor(_)
+            ))
+        ));
+        com.reconnect(&ReconnectPolicy::default()).unwrap();
+        drop(com);
+        server.join().unwrap();
+
+        let transcript = Transcript::read_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let inbound: String = transcript.inbound().map(|e| e.data.as_str()).collect();
+        assert!(
+            inbound.ends_with("<protocol><joined roomId=\"room-1\"/>"),
+            "{:?}",
+            inbound
+        );
+        assert!(!inbound.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_close_sends_close_and_ends_protocol() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"<protocol><joined roomId=\"room-1\"/>")
+                .unwrap();
+
+            let mut received = String::new();
+            let mut tmp = [0_u8; 1024];
+            while !received.contains("</protocol>") {
+                let n = stream.read(&mut tmp).unwrap();
+                if n == 0 {
+                    break;
+                }
+                received.push_str(&String::from_utf8_lossy(&tmp[..n]));
+            }
+            stream
+                .write_all(b"<left roomId=\"room-1\"/></protocol>")
+                .unwrap();
+            received
+        });
+
+        let mut com: ComHandler = ComHandler::join(&addr, None).unwrap();
+        let msg = com.wait_for_com_message(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            msg,
+            ComMessage::Joined(Joined {
+                room_id: "room-1".to_string()
+            })
+        );
+
+        let drained = com.close().unwrap();
+        assert!(drained
+            .iter()
+            .any(|msg| matches!(msg, ComMessage::Left(left) if left.room_id == "room-1")));
+        assert!(com.send_raw("<ping/>").is_err());
+
+        let received = server.join().unwrap();
+        assert!(received.starts_with("<protocol><join gameType=\"swc_2026_piranhas\"/>"));
+        assert!(received.ends_with("<close></close></protocol>"));
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    use socha::{
+        debugger::{DebugStep, StepDebugger},
+        i_client_handler::{start_iclient_with_config, ClientConfig, ClientSummary},
+        mock_server::{MockServer, MockServerConfig},
+        neutral::Team,
+        season::{piranhas::Piranhas, Season},
+    };
+
+    use crate::common::{start_mock, FirstMoveBot};
+
+    /// prepares a paused room and seats two bots in it
+    fn start_debugger(mock: &MockServer) -> (StepDebugger, Vec<JoinHandle<ClientSummary>>) {
+        let debugger = StepDebugger::prepare(
+            mock.addr(),
+            &Piranhas::game_type(),
+            &MockServerConfig::default().password,
+            ("one", "two"),
+        )
+        .unwrap();
+        let (one, two) = debugger.reservations().clone();
+        let players = [one, two]
+            .into_iter()
+            .map(|reservation| {
+                let config = ClientConfig {
+                    addr: mock.addr().to_string(),
+                    reservation_code: Some(reservation),
+                    ..Default::default()
+                };
+                thread::spawn(move || {
+                    start_iclient_with_config(&config, &mut FirstMoveBot).unwrap()
+                })
+            })
+            .collect();
+        (debugger, players)
+    }
+
+    #[test]
+    fn test_steps_a_paused_game() {
+        let mock = start_mock(4, Duration::from_secs(2));
+        let (mut debugger, players) = start_debugger(&mock);
+
+        let start = debugger.wait_for_start(Duration::from_secs(5)).unwrap();
+        assert_eq!(start.state.turn, 0);
+        assert_eq!(start.moves_one, start.state.possible_moves());
+        assert_eq!(start.moves_two, start.state.possible_moves_for(Team::Two));
+        assert_eq!(start.eval, start.state.evaluate(Team::One));
+        assert_eq!(start.state.evaluate(Team::Two), -start.eval);
+        // paused, the first step still plays turn 1
+        thread::sleep(Duration::from_millis(200));
+
+        for turn in 1..=2 {
+            match debugger.step().unwrap() {
+                DebugStep::Moved(view) => {
+                    assert_eq!(view.state.turn, turn);
+                    assert!(view.to_string().contains("eval"));
+                }
+                other => panic!("expected a move, got {:?}", other),
+            }
+        }
+        assert_eq!(debugger.history().len(), 3);
+
+        let result = debugger.resume(Duration::from_secs(5)).unwrap();
+        for player in players {
+            assert_eq!(player.join().unwrap().result, Some(result.clone()));
+        }
+    }
+
+    #[test]
+    fn test_cancelled_game_ends_the_players() {
+        let mock = start_mock(4, Duration::from_secs(2));
+        let (mut debugger, players) = start_debugger(&mock);
+        debugger.wait_for_start(Duration::from_secs(5)).unwrap();
+        debugger.cancel().unwrap();
+        for player in players {
+            let summary = player.join().unwrap();
+            assert!(summary.result.is_none());
+            assert_eq!(summary.moves_sent, 0);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use socha::{
+        error::InferMoveErr,
+        internal::{FieldDiff, GameState},
+        neutral::PiranhaField,
+    };
+
+    use crate::common::start_state;
+
+    #[test]
+    fn test_infer_every_legal_move() {
+        let mut state = start_state();
+        // a few turns into the game, so fish can be eaten as well
+        for _ in 0..10 {
+            for mv in state.possible_moves() {
+                let mut after = state.clone();
+                after.perform_move(mv);
+                after.last_move = None;
+                assert_eq!(GameState::infer_move(&state, &after), Ok(mv));
+            }
+            let mv = *state.possible_moves().last().unwrap();
+            state.perform_move(mv);
+        }
+    }
+
+    #[test]
+    fn test_diff_and_errors() {
+        let before = start_state();
+        assert_eq!(
+            GameState::infer_move(&before, &before),
+            Err(InferMoveErr::Unchanged)
+        );
+
+        let mv = before.possible_moves()[0];
+        let mut after = before.clone();
+        after.perform_move(mv);
+        let diff = before.board.diff(&after.board);
+        assert_eq!(diff.len(), 2);
+        assert!(diff
+            .iter()
+            .any(|d| d.pos == mv.from && d.after == PiranhaField::Empty));
+
+        // a squid appears, no move does that
+        let mut impossible = after.clone();
+        *impossible.board.get_mut(5, 5) = PiranhaField::Squid;
+        let mut expected_diff = diff.clone();
+        expected_diff.push(FieldDiff {
+            pos: (5, 5),
+            before: PiranhaField::Empty,
+            after: PiranhaField::Squid,
+        });
+        expected_diff.sort_by_key(|d| (d.pos.1, d.pos.0));
+        assert_eq!(
+            GameState::infer_move(&before, &impossible),
+            Err(InferMoveErr::NoLegalMove(expected_diff))
+        );
+    }
+}
@@ -0,0 +1,334 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        io::Write,
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use socha::{
+        error::{HandlerErr, ReceiveErr},
+        i_client_handler::{
+            cancel::CancelReason,
+            handler_trait::{HandlerControl, IClientHandler},
+            middleware::{Interceptor, Middleware, MoveGuard},
+            move_context::MoveContext,
+            script::{run_script_with_middleware, Callback, Script},
+            start_iclient_with_middleware, ClientConfig, SendCommnad,
+        },
+        internal::{
+            ComMessage, GameResult, GameState, RoomMessage, ServerError, ServerErrorReason,
+        },
+        neutral::{Direction, Move, Team},
+    };
+
+    #[cfg(feature = "tools")]
+    use crate::common::start_mock;
+    use crate::common::start_state;
+
+    /// plays `fixed` if set, the first legal move otherwise
+    struct Bot {
+        fixed: Option<Move>,
+    }
+
+    impl IClientHandler for Bot {
+        fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+            Ok(self.fixed.unwrap_or(ctx.state.possible_moves()[0]))
+        }
+
+        fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+            HandlerControl::Continue
+        }
+    }
+
+    /// writes its name and the direction into a shared log, drops server errors if `veto_errors`
+    struct Tracer {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+        veto_errors: bool,
+    }
+
+    impl Interceptor for Tracer {
+        fn inbound(&mut self, msg: ComMessage, _received_at: Instant) -> Option<ComMessage> {
+            self.log.lock().unwrap().push(format!("{} <-", self.name));
+            if self.veto_errors && matches!(msg, ComMessage::Error(_)) {
+                return None;
+            }
+            Some(msg)
+        }
+
+        fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+            self.log.lock().unwrap().push(format!("{} ->", self.name));
+            Some(cmd)
+        }
+    }
+
+    /// time from each move request to the move leaving the client
+    #[cfg(feature = "tools")]
+    struct LatencyProbe {
+        requested_at: Option<Instant>,
+        latencies: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    #[cfg(feature = "tools")]
+    impl Interceptor for LatencyProbe {
+        fn inbound(&mut self, msg: ComMessage, received_at: Instant) -> Option<ComMessage> {
+            if let ComMessage::Room { msg: room_msg, .. } = &msg {
+                if matches!(**room_msg, RoomMessage::MoveRequest) {
+                    self.requested_at = Some(received_at);
+                }
+            }
+            Some(msg)
+        }
+
+        fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+            if let (SendCommnad::Move(_), Some(at)) = (&cmd, self.requested_at.take()) {
+                self.latencies.lock().unwrap().push(at.elapsed());
+            }
+            Some(cmd)
+        }
+    }
+
+    fn server_error() -> ServerError {
+        ServerError {
+            reason: ServerErrorReason::Other,
+            message: "something".to_string(),
+            original_request: None,
+            room_id: None,
+        }
+    }
+
+    #[test]
+    fn test_move_guard_replaces_illegal_moves() {
+        let start = start_state();
+        let legal = start.possible_moves()[0];
+        let mut after = start.clone();
+        after.perform_move(legal);
+        let script = Script::new("room")
+            .joined()
+            .welcome(Team::One)
+            .memento(start)
+            .move_request()
+            .memento(after);
+        // there is no fish in the corner
+        let mut bot = Bot {
+            fixed: Some(Move {
+                from: (0, 0),
+                dir: Direction::UP,
+            }),
+        };
+        let middleware = Middleware::new().with(MoveGuard::new());
+        let report =
+            run_script_with_middleware(&ClientConfig::default(), script, &mut bot, middleware);
+
+        assert_eq!(report.sent_moves(), vec![legal]);
+        assert_eq!(report.summary.moves_sent, 1);
+        // the tracker knows the corrected move
+        assert_eq!(report.summary.desyncs, 0);
+    }
+
+    #[test]
+    fn test_chain_order_and_veto() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let middleware = Middleware::new()
+            .with(Tracer {
+                name: "outer",
+                log: log.clone(),
+                veto_errors: false,
+            })
+            .with(Tracer {
+                name: "inner",
+                log: log.clone(),
+                veto_errors: true,
+            });
+        let script = Script::new("room")
+            .joined()
+            .welcome(Team::One)
+            .memento(start_state())
+            .error(server_error())
+            .move_request();
+        let mut bot = Bot { fixed: None };
+        let report =
+            run_script_with_middleware(&ClientConfig::default(), script, &mut bot, middleware);
+
+        assert!(!report
+            .callbacks
+            .iter()
+            .any(|cb| matches!(cb, Callback::ServerError(_))));
+        assert_eq!(report.sent_moves().len(), 1);
+        let log = log.lock().unwrap();
+        let inbound: Vec<&str> = ["outer <-", "inner <-"].repeat(5);
+        assert_eq!(log[..10], inbound[..]);
+        assert_eq!(log[10..], ["inner ->", "outer ->"]);
+    }
+
+    #[test]
+    #[cfg(feature = "tools")]
+    fn test_latency_probe_in_a_real_game() {
+        use socha::{
+            admin::AdminClient,
+            mock_server::MockServerConfig,
+            season::{piranhas::Piranhas, Season},
+            socha_com::PrepareSlot,
+        };
+
+        let mock = start_mock(4, Duration::from_secs(2));
+        let mut admin: AdminClient = AdminClient::connect_and_authenticate(
+            mock.addr(),
+            &Piranhas::game_type(),
+            &MockServerConfig::default().password,
+        )
+        .unwrap();
+        let slots = [
+            PrepareSlot::new("one".to_string(), true, true),
+            PrepareSlot::new("two".to_string(), true, true),
+        ];
+        let (one, two) = admin.prepare(false, &slots).unwrap().reservations;
+
+        let latencies = Arc::new(Mutex::new(Vec::new()));
+        let players: Vec<_> = [one, two]
+            .into_iter()
+            .map(|reservation| {
+                let config = ClientConfig {
+                    addr: mock.addr().to_string(),
+                    reservation_code: Some(reservation),
+                    ..Default::default()
+                };
+                let middleware = Middleware::new().with(LatencyProbe {
+                    requested_at: None,
+                    latencies: latencies.clone(),
+                });
+                thread::spawn(move || {
+                    start_iclient_with_middleware(&config, &mut Bot { fixed: None }, middleware)
+                        .unwrap()
+                })
+            })
+            .collect();
+        let moves_sent: u32 = players
+            .into_iter()
+            .map(|player| player.join().unwrap().moves_sent)
+            .sum();
+
+        assert_eq!(moves_sent, 4);
+        let latencies = latencies.lock().unwrap();
+        assert_eq!(latencies.len(), 4);
+        assert!(latencies.iter().all(|l| *l < Duration::from_secs(1)));
+    }
+
+    /// drops every move
+    struct MoveVeto;
+
+    impl Interceptor for MoveVeto {
+        fn outbound(&mut self, cmd: SendCommnad) -> Option<SendCommnad> {
+            match cmd {
+                SendCommnad::Move(_) => None,
+                cmd => Some(cmd),
+            }
+        }
+    }
+
+    #[test]
+    fn test_dropped_move_skips_while_waiting() {
+        let script = Script::new("room")
+            .joined()
+            .welcome(Team::One)
+            .memento(start_state())
+            .move_request();
+        let mut bot = Bot { fixed: None };
+        let middleware = Middleware::new().with(MoveVeto);
+        let report =
+            run_script_with_middleware(&ClientConfig::default(), script, &mut bot, middleware);
+
+        assert!(report.sent_moves().is_empty());
+        assert_eq!(report.summary.moves_sent, 0);
+        assert!(report.fired(&Callback::CalculateMove { turn: 0 }));
+        assert!(!report
+            .callbacks
+            .iter()
+            .any(|cb| matches!(cb, Callback::WhileWaiting { .. })));
+    }
+
+    /// drops every game result
+    struct ResultVeto;
+
+    impl Interceptor for ResultVeto {
+        fn inbound(&mut self, msg: ComMessage, _received_at: Instant) -> Option<ComMessage> {
+            if let ComMessage::Room { msg: room_msg, .. } = &msg {
+                if matches!(**room_msg, RoomMessage::Result(_)) {
+                    return None;
+                }
+            }
+            Some(msg)
+        }
+    }
+
+    #[test]
+    fn test_script_cancels_ignore_dropped_messages() {
+        let start = start_state();
+        let mut after = start.clone();
+        after.perform_move(start.possible_moves()[0]);
+        let script = Script::new("room")
+            .joined()
+            .welcome(Team::One)
+            .memento(start)
+            .move_request()
+            .memento(after)
+            .result(GameResult {
+                player1_result: Vec::new(),
+                player2_result: Vec::new(),
+                winner: None,
+            })
+            .left();
+        let mut bot = Bot { fixed: None };
+        let middleware = Middleware::new().with(ResultVeto);
+        let report =
+            run_script_with_middleware(&ClientConfig::default(), script, &mut bot, middleware);
+
+        // the reader never saw the result, the next cancelling message it kept is the leave
+        assert!(report.fired(&Callback::WhileWaiting {
+            turn: 1,
+            cancel: Some(CancelReason::RoomLeft),
+        }));
+        assert!(!report.fired(&Callback::GameResult));
+        assert_eq!(report.summary.result, None);
+    }
+
+    /// panics on the first inbound message
+    struct Exploding;
+
+    impl Interceptor for Exploding {
+        fn inbound(&mut self, _msg: ComMessage, _received_at: Instant) -> Option<ComMessage> {
+            panic!("interceptor exploded");
+        }
+    }
+
+    #[test]
+    fn test_panicking_interceptor_ends_the_client_with_an_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"<protocol><joined roomId=\"room\"/>")
+                .unwrap();
+            thread::sleep(Duration::from_millis(200));
+        });
+
+        let config = ClientConfig {
+            addr,
+            ..Default::default()
+        };
+        let middleware = Middleware::new().with(Exploding);
+        let res = start_iclient_with_middleware(&config, &mut Bot { fixed: None }, middleware);
+        server.join().unwrap();
+
+        match res {
+            Err(ReceiveErr::ReaderPanicked(msg)) => assert_eq!(msg, "interceptor exploded"),
+            other => panic!("expected a reader panic, got {:?}", other),
+        }
+    }
+}
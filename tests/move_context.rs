@@ -0,0 +1,40 @@
+#[cfg(test)]
+pub mod tests {
+    use std::time::{Duration, Instant};
+
+    use socha::{i_client_handler::move_context::MoveContext, internal::GameState, neutral::Team};
+
+    #[test]
+    fn test_deadline_and_budget() {
+        let received_at = Instant::now();
+        let ctx = MoveContext::new(
+            received_at,
+            Duration::from_secs(2),
+            Some(Team::One),
+            GameState::default(),
+        );
+        assert_eq!(ctx.deadline, received_at + Duration::from_secs(2));
+        assert!(!ctx.is_past_deadline());
+        assert!(ctx.time_left() <= Duration::from_secs(2));
+        assert!(ctx.budget(Duration::from_millis(500)) <= Duration::from_millis(1500));
+        assert_eq!(
+            ctx.soft_deadline(Duration::from_millis(300)),
+            received_at + Duration::from_millis(1700)
+        );
+        // a margin larger than the limit leaves no time at all
+        assert_eq!(ctx.soft_deadline(Duration::from_secs(10)), received_at);
+        assert_eq!(ctx.budget(Duration::from_secs(10)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_past_deadline() {
+        let ctx = MoveContext::new(
+            Instant::now() - Duration::from_secs(3),
+            Duration::from_secs(2),
+            None,
+            GameState::default(),
+        );
+        assert!(ctx.is_past_deadline());
+        assert_eq!(ctx.time_left(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,65 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use socha::{
+        error::OrchestratorErr,
+        mock_server::MockServer,
+        orchestrator::{BotCommand, MatchConfig, Orchestrator},
+    };
+
+    use crate::common::{example_bot, start_mock};
+
+    fn start_match_mock() -> (MockServer, MatchConfig) {
+        let mock = start_mock(6, Duration::from_secs(2));
+        let config = MatchConfig {
+            addr: mock.addr().to_string(),
+            game_timeout: Duration::from_secs(30),
+            ..Default::default()
+        };
+        (mock, config)
+    }
+
+    #[test]
+    fn test_batch_against_mock_server() {
+        let program = example_bot();
+        let working_dir = std::env::temp_dir();
+        let mut bot_a = BotCommand::new("a", &program);
+        bot_a.working_dir = Some(working_dir.clone());
+        let mut bot_b = BotCommand::new("b", &program);
+        bot_b.working_dir = Some(working_dir);
+
+        let (_mock, config) = start_match_mock();
+        let mut orchestrator: Orchestrator = Orchestrator::connect(config).unwrap();
+        let report = orchestrator.run_batch(&bot_a, &bot_b, 2);
+
+        assert_eq!(report.matches.len(), 2);
+        assert_eq!(report.failed(), 0, "{:?}", report.matches);
+        let first = report.matches[0].as_ref().unwrap();
+        let second = report.matches[1].as_ref().unwrap();
+        assert_ne!(first.room_id, second.room_id);
+        assert_eq!(first.players, ("a".to_string(), "b".to_string()));
+        assert_eq!(second.players, ("b".to_string(), "a".to_string()));
+        assert_eq!(report.wins("a") + report.wins("b") + report.draws(), 2);
+        for m in [first, second] {
+            assert_eq!(m.result.player1_result.len(), 1);
+            assert!(m.exit_status.0.is_some_and(|s| s.success()));
+            assert!(m.exit_status.1.is_some_and(|s| s.success()));
+        }
+    }
+
+    #[test]
+    fn test_missing_bot_executable() {
+        let (_mock, config) = start_match_mock();
+        let mut orchestrator: Orchestrator = Orchestrator::connect(config).unwrap();
+        let missing = BotCommand::new("missing", "/nonexistent/bot");
+        match orchestrator.run_match(&missing, &missing) {
+            Err(OrchestratorErr::SpawnBot { program, .. }) => {
+                assert_eq!(program, "/nonexistent/bot")
+            }
+            other => panic!("expected a spawn error, got {:?}", other),
+        }
+    }
+}
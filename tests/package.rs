@@ -0,0 +1,154 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{fs, io::Cursor, path::PathBuf};
+
+    use socha::{
+        error::PackageErr,
+        package::{
+            check_layout, exe_suffix, start_script, target_dir, unpack, verify_package,
+            write_package,
+            zip::{read_zip, write_zip, ZipEntry},
+            START_SCRIPT_NAME,
+        },
+    };
+
+    use crate::common::example_bot;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("socha-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(name: &str, mode: u32, data: &str) -> ZipEntry {
+        ZipEntry {
+            name: name.to_string(),
+            mode,
+            data: data.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let entries = vec![
+            entry(START_SCRIPT_NAME, 0o755, &start_script("bot")),
+            entry("bot", 0o755, &"binary".repeat(100)),
+            entry("books/opening.txt", 0o644, ""),
+        ];
+        let mut bytes = Cursor::new(Vec::new());
+        write_zip(&mut bytes, &entries).unwrap();
+        let mut bytes = bytes.into_inner();
+        assert_eq!(read_zip(&bytes).unwrap(), entries);
+
+        // a changed crc32 in the central directory no longer matches the data
+        let central = bytes.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        bytes[central + 16] ^= 0xff;
+        assert!(read_zip(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_zip_limits() {
+        let long_name = entry(&"a".repeat(usize::from(u16::MAX) + 1), 0o644, "");
+        assert!(matches!(
+            write_zip(Cursor::new(Vec::new()), &[long_name]),
+            Err(PackageErr::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_unpack_rejects_paths_outside_before_writing() {
+        let dir = temp_dir("traversal");
+        let zip_path = dir.join("bad.zip");
+        let entries = [
+            entry(START_SCRIPT_NAME, 0o755, &start_script("bot")),
+            entry("bot", 0o755, ""),
+            entry("../escaped", 0o644, ""),
+        ];
+        write_zip(fs::File::create(&zip_path).unwrap(), &entries).unwrap();
+
+        let work_dir = dir.join("unpacked");
+        fs::create_dir_all(&work_dir).unwrap();
+        assert!(matches!(
+            unpack(&zip_path, &work_dir),
+            Err(PackageErr::InvalidPackage(_))
+        ));
+        assert_eq!(fs::read_dir(&work_dir).unwrap().count(), 0);
+        assert!(!dir.join("escaped").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_target_dir_and_exe_suffix() {
+        // the test executable lives in <target dir>/<profile>/deps
+        let exe = std::env::current_exe().unwrap();
+        let expected = exe.ancestors().nth(3).unwrap();
+        let found = target_dir(&PathBuf::from(env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(
+            found.canonicalize().unwrap(),
+            expected.canonicalize().unwrap()
+        );
+
+        assert_eq!(exe_suffix(Some("x86_64-pc-windows-msvc")), ".exe");
+        assert_eq!(exe_suffix(Some("x86_64-unknown-linux-gnu")), "");
+        assert_eq!(exe_suffix(None), std::env::consts::EXE_SUFFIX);
+    }
+
+    #[test]
+    fn test_check_layout() {
+        let script = entry(START_SCRIPT_NAME, 0o755, &start_script("bot"));
+        assert!(check_layout(&[script.clone(), entry("bot", 0o755, "")]).is_ok());
+
+        let invalid = [
+            vec![entry("bot", 0o755, "")],
+            vec![script.clone()],
+            vec![script.clone(), entry("bot", 0o644, "")],
+            vec![
+                entry(START_SCRIPT_NAME, 0o644, &start_script("bot")),
+                entry("bot", 0o755, ""),
+            ],
+            vec![
+                entry(
+                    START_SCRIPT_NAME,
+                    0o755,
+                    &start_script("bot").replace('\n', "\r\n"),
+                ),
+                entry("bot", 0o755, ""),
+            ],
+        ];
+        for entries in invalid {
+            assert!(
+                matches!(check_layout(&entries), Err(PackageErr::InvalidPackage(_))),
+                "{:?} should be rejected",
+                entries.iter().map(|e| &e.name).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_packaged_bot_plays_on_mock_server() {
+        let dir = temp_dir("package");
+        let config_file = dir.join("bot.toml");
+        fs::write(&config_file, "depth = 3\n").unwrap();
+        let zip_path = dir.join("bot.zip");
+        write_package(&example_bot(), &[config_file], &zip_path).unwrap();
+
+        let names: Vec<String> = read_zip(&fs::read(&zip_path).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![START_SCRIPT_NAME, "i_client_handler_example", "bot.toml"]
+        );
+
+        let work_dir = dir.join("unpacked");
+        let report = verify_package(&zip_path, &work_dir).unwrap();
+        assert!(work_dir.join("bot.toml").is_file());
+        assert!(report.result.winner.is_none_or(|w| w.regular));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,75 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::Cell;
+
+    use socha::{i_client_handler::ponder::Ponderer, internal::GameState};
+
+    use crate::common::start_state;
+
+    /// stands in for a real search, counts the legal moves of the position
+    fn count_moves(state: &GameState, depth: u32) -> (usize, u32) {
+        (state.possible_moves().len(), depth)
+    }
+
+    #[test]
+    fn test_ponders_every_reply_and_reuses_the_played_one() {
+        let start = start_state();
+        let mut after_our_move = start.clone();
+        after_our_move.perform_move(start.possible_moves()[0]);
+        let replies = after_our_move.possible_moves();
+
+        let mut ponderer = Ponderer::new();
+        ponderer.max_depth = 2;
+        ponderer.ponder(
+            &after_our_move,
+            || false,
+            |s, depth, _| count_moves(s, depth),
+        );
+        assert_eq!(ponderer.results().len(), replies.len());
+        assert!(ponderer.results().iter().all(|p| p.depth == 2));
+
+        let played = replies[3];
+        let mut expected = after_our_move.clone();
+        expected.perform_move(played);
+        let pondered = ponderer.take(played).unwrap();
+        assert_eq!(pondered.reply, played);
+        assert_eq!(pondered.result, (expected.possible_moves().len(), 2));
+        assert!(ponderer.results().is_empty());
+    }
+
+    #[test]
+    fn test_stops_and_continues_where_it_stopped() {
+        let start = start_state();
+        let replies = start.possible_moves();
+        let searches = Cell::new(0);
+        let mut ponderer = Ponderer::new();
+        ponderer.max_depth = 1;
+
+        // stopped after five searches, the fifth was interrupted and is dropped
+        ponderer.ponder(
+            &start,
+            || searches.get() >= 5,
+            |s, depth, _| {
+                searches.set(searches.get() + 1);
+                count_moves(s, depth)
+            },
+        );
+        assert_eq!(ponderer.results().len(), 4);
+
+        // the same position again only searches the missing replies
+        searches.set(0);
+        ponderer.ponder(
+            &start,
+            || false,
+            |s, depth, _| {
+                searches.set(searches.get() + 1);
+                count_moves(s, depth)
+            },
+        );
+        assert_eq!(searches.get(), replies.len() - 4);
+        assert_eq!(ponderer.results().len(), replies.len());
+        assert!(ponderer.get(replies[0]).is_some());
+    }
+}
@@ -0,0 +1,193 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    use socha::{
+        error::HandlerErr,
+        i_client_handler::{
+            cancel::{CancelReason, CancelToken},
+            handler_trait::{HandlerControl, IClientHandler},
+            move_context::MoveContext,
+            script::{run_script, Callback, Script},
+            start_iclient_with_config, ClientConfig, ClientEnd,
+        },
+        internal::{GameResult, GameState, Winner},
+        neutral::{Move, Team},
+    };
+
+    use crate::common::start_state;
+
+    /// plays the first legal move, fails the requests listed in `fail_on`
+    struct ScriptedBot {
+        fail_on: Vec<u32>,
+        requests: u32,
+        on_result: HandlerControl,
+    }
+
+    impl ScriptedBot {
+        fn new() -> Self {
+            ScriptedBot {
+                fail_on: Vec::new(),
+                requests: 0,
+                on_result: HandlerControl::Continue,
+            }
+        }
+    }
+
+    impl IClientHandler for ScriptedBot {
+        fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+            self.requests += 1;
+            if self.fail_on.contains(&self.requests) {
+                return Err("no idea".into());
+            }
+            std::thread::sleep(Duration::from_millis(10));
+            Ok(ctx.state.possible_moves()[0])
+        }
+
+        fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+            HandlerControl::Continue
+        }
+
+        fn on_game_result(&mut self, _res: &GameResult) -> HandlerControl {
+            self.on_result
+        }
+
+        fn while_waiting(&mut self, _state: &GameState, cancel: CancelToken) {
+            // returns right away, the token is cancelled already
+            cancel.wait();
+        }
+    }
+
+    fn result() -> GameResult {
+        GameResult {
+            player1_result: Vec::new(),
+            player2_result: Vec::new(),
+            winner: Some(Winner {
+                team: Team::One,
+                regular: true,
+                reason: None,
+            }),
+        }
+    }
+
+    /// two full rounds with team one, the opponent always plays its first legal move
+    fn two_rounds() -> (Script, Vec<GameState>) {
+        let mut states = vec![start_state()];
+        for _ in 0..3 {
+            let mut next = states.last().unwrap().clone();
+            next.perform_move(next.possible_moves()[0]);
+            states.push(next);
+        }
+        let script = Script::new("room")
+            .joined()
+            .welcome(Team::One)
+            .memento(states[0].clone())
+            .move_request()
+            .memento(states[1].clone())
+            .memento(states[2].clone())
+            .move_request()
+            .memento(states[3].clone())
+            .result(result())
+            .left();
+        (script, states)
+    }
+
+    #[test]
+    fn test_script_records_callbacks_and_moves() {
+        let (script, states) = two_rounds();
+        let mut bot = ScriptedBot::new();
+        let report = run_script(&ClientConfig::default(), script, &mut bot);
+
+        assert_eq!(
+            report.callbacks,
+            vec![
+                Callback::Connected,
+                Callback::GameJoined("room".to_string()),
+                Callback::WelcomeMessage(Some(Team::One)),
+                Callback::GamestateUpdate { turn: 0 },
+                Callback::CalculateMove { turn: 0 },
+                Callback::WhileWaiting {
+                    turn: 1,
+                    cancel: Some(CancelReason::MoveRequest)
+                },
+                Callback::GamestateUpdate { turn: 1 },
+                Callback::OpponentMove(states[2].last_move.unwrap()),
+                Callback::GamestateUpdate { turn: 2 },
+                Callback::CalculateMove { turn: 2 },
+                Callback::WhileWaiting {
+                    turn: 3,
+                    cancel: Some(CancelReason::GameOver)
+                },
+                Callback::GamestateUpdate { turn: 3 },
+                Callback::GameResult,
+                Callback::GameLeft,
+            ]
+        );
+        assert_eq!(report.moves.len(), 2);
+        assert!(report
+            .moves
+            .iter()
+            .all(|mv| mv.took >= Duration::from_millis(10)));
+        assert_eq!(
+            report.sent_moves(),
+            vec![states[1].last_move.unwrap(), states[3].last_move.unwrap()]
+        );
+        assert_eq!(report.summary.moves_sent, 2);
+        assert_eq!(report.summary.desyncs, 0);
+        assert_eq!(report.summary.result, Some(result()));
+        assert_eq!(report.summary.end, ClientEnd::ServerEnded);
+    }
+
+    #[test]
+    fn test_script_failed_move_and_leave() {
+        let (script, states) = two_rounds();
+        let mut bot = ScriptedBot::new();
+        bot.fail_on = vec![1];
+        bot.on_result = HandlerControl::Leave;
+        let report = run_script(&ClientConfig::default(), script, &mut bot);
+
+        assert_eq!(report.moves[0].turn, 0);
+        assert_eq!(report.moves[0].result, Err("no idea".to_string()));
+        assert!(report
+            .callbacks
+            .iter()
+            .any(|cb| matches!(cb, Callback::Error(e) if e.contains("no idea"))));
+        // the fallback is the first legal move as well
+        assert_eq!(report.sent_moves()[0], states[1].last_move.unwrap());
+        // left after the result, `left` is not dispatched anymore
+        assert!(!report.fired(&Callback::GameLeft));
+        assert_eq!(report.summary.end, ClientEnd::HandlerLeft);
+    }
+
+    /// holds an `Rc`, so it is not `Send`
+    struct SharedLogBot {
+        turns: Rc<RefCell<Vec<u32>>>,
+    }
+
+    impl IClientHandler for SharedLogBot {
+        fn calculate_move(&mut self, ctx: &MoveContext) -> Result<Move, HandlerErr> {
+            self.turns.borrow_mut().push(ctx.state.turn);
+            Ok(ctx.state.possible_moves()[0])
+        }
+
+        fn on_gamestate_update(&mut self, _state: GameState) -> HandlerControl {
+            HandlerControl::Continue
+        }
+    }
+
+    #[test]
+    fn test_script_runs_handlers_that_are_not_send() {
+        let (script, _) = two_rounds();
+        let turns = Rc::new(RefCell::new(Vec::new()));
+        let mut bot = SharedLogBot {
+            turns: turns.clone(),
+        };
+        let report = run_script(&ClientConfig::default(), script, &mut bot);
+        assert_eq!(report.summary.moves_sent, 2);
+        assert_eq!(*turns.borrow(), vec![0, 2]);
+        // the socket runtime does not need `Send` either, only its anytime mode does
+        let _ = start_iclient_with_config::<SharedLogBot>;
+    }
+}
@@ -0,0 +1,57 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use socha::{
+        error::ReceiveErr,
+        i_client_handler::{start_iclient, supports_game_type},
+        neutral::{Direction, Move},
+        season::{piranhas::Piranhas, GameType, Season},
+    };
+
+    use crate::common::FirstMoveBot;
+
+    #[test]
+    fn test_game_type_round_trip() {
+        let game_type = GameType::from_str("swc_2025_mississippi_queen").unwrap();
+        assert_eq!(game_type, GameType::new(2025, "mississippi_queen"));
+        assert_eq!(game_type.to_string(), "swc_2025_mississippi_queen");
+        assert_eq!(Piranhas::game_type().to_string(), "swc_2026_piranhas");
+
+        assert!(GameType::from_str("piranhas").is_err());
+        assert!(GameType::from_str("swc_twenty_piranhas").is_err());
+    }
+
+    #[test]
+    fn test_piranhas_move_data() {
+        let mv = Move {
+            from: (3, 7),
+            dir: Direction::DownLeft,
+        };
+        let xml = Piranhas::make_move_data_xml(&mv).unwrap();
+        assert_eq!(
+            xml,
+            r#"<data class="move"><from x="3" y="7"/><direction>DOWN_LEFT</direction></data>"#
+        );
+    }
+
+    #[test]
+    fn test_client_rejects_other_games() {
+        assert!(supports_game_type(&Piranhas::game_type()));
+        assert!(supports_game_type(&GameType::new(2027, "piranhas")));
+        let hase = GameType::new(2025, "hase");
+        assert!(!supports_game_type(&hase));
+
+        // rejected before connecting, nothing listens on this port
+        let res = start_iclient(
+            "127.0.0.1:1",
+            None,
+            &hase,
+            &mut FirstMoveBot,
+            Duration::from_millis(10),
+        );
+        assert!(matches!(res, Err(ReceiveErr::UnsupportedGameType(t)) if t == hase));
+    }
+}
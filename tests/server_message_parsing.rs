@@ -5,7 +5,7 @@ pub mod tests {
             ReceivedAggregation, ReceivedComMessage, ReceivedData, ReceivedFragment,
             ReceivedRelevantForRanking,
         },
-        internal::{RoomMessage, Row},
+        internal::{RoomMessage, Row, ServerError, ServerErrorReason, WelcomeMessage},
         neutral::{PiranhaField, Size, Team},
     };
     use strong_xml::XmlRead;
@@ -354,4 +354,103 @@ pub mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_parse_error_packet() {
+        let xml = r#"
+        <comMessage>
+        <errorpacket message="Unknown reservation code abc">
+            <originalRequest class="joinPrepared" reservationCode="abc"/>
+        </errorpacket>
+        </comMessage>
+        "#;
+
+        let received_com_message = ReceivedComMessage::from_str(xml).unwrap();
+        let err = ServerError::from(received_com_message.error_packet.unwrap());
+        assert_eq!(err.reason, ServerErrorReason::UnknownReservationCode);
+        assert_eq!(err.message, "Unknown reservation code abc");
+        assert_eq!(err.original_request, Some("joinPrepared".to_string()));
+        assert_eq!(err.room_id, None);
+    }
+
+    #[test]
+    fn test_parse_room_error() {
+        let xml = r#"
+        <comMessage>
+        <room roomId="b5f43e86-df4e-4221-b83d-337497950ac1">
+            <data class="error" message="invalid move: the fish cannot jump over an opponent">
+                <originalMessage class="move">
+                    <from x="0" y="3"/>
+                    <direction>RIGHT</direction>
+                </originalMessage>
+            </data>
+        </room>
+        </comMessage>
+        "#;
+
+        let mut received_com_message = ReceivedComMessage::from_str(xml).unwrap();
+        let err = ServerError::try_from(received_com_message.room.remove(0)).unwrap();
+        assert_eq!(err.reason, ServerErrorReason::InvalidMove);
+        assert_eq!(err.original_request, Some("move".to_string()));
+        assert_eq!(
+            err.room_id,
+            Some("b5f43e86-df4e-4221-b83d-337497950ac1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_welcome_message() {
+        let xml = r#"
+        <comMessage>
+        <room roomId="b5f43e86-df4e-4221-b83d-337497950ac1">
+            <data class="welcomeMessage" team="TWO"/>
+        </room>
+        </comMessage>
+        "#;
+
+        let mut received_com_message = ReceivedComMessage::from_str(xml).unwrap();
+        let room = RoomMessage::try_from(received_com_message.room.remove(0)).unwrap();
+        assert_eq!(
+            room,
+            RoomMessage::WelcomeMessage(WelcomeMessage {
+                team: Some(Team::Two)
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_welcome_message_unknown_team() {
+        let xml = r#"
+        <comMessage>
+        <room roomId="b5f43e86-df4e-4221-b83d-337497950ac1">
+            <data class="welcomeMessage" team="PURPLE"/>
+        </room>
+        </comMessage>
+        "#;
+
+        let mut received_com_message = ReceivedComMessage::from_str(xml).unwrap();
+        let room = RoomMessage::try_from(received_com_message.room.remove(0)).unwrap();
+        assert_eq!(
+            room,
+            RoomMessage::WelcomeMessage(WelcomeMessage { team: None })
+        );
+    }
+
+    #[test]
+    fn test_parse_welcome_message_without_team() {
+        let xml = r#"
+        <comMessage>
+        <room roomId="b5f43e86-df4e-4221-b83d-337497950ac1">
+            <data class="welcomeMessage"/>
+        </room>
+        </comMessage>
+        "#;
+
+        let mut received_com_message = ReceivedComMessage::from_str(xml).unwrap();
+        let room = RoomMessage::try_from(received_com_message.room.remove(0)).unwrap();
+        assert_eq!(
+            room,
+            RoomMessage::WelcomeMessage(WelcomeMessage { team: None })
+        );
+    }
 }
@@ -0,0 +1,83 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use socha::{
+        season::{piranhas::Piranhas, Season},
+        spectator::{RoomTracker, SpectatorEvent},
+        transcript::{replay_messages, TrafficDirection, Transcript, TranscriptEntry},
+    };
+
+    use crate::common::memento_xml;
+
+    fn result_xml(room_id: &str) -> String {
+        format!(
+            "<room roomId=\"{}\"><data class=\"result\"><definition><fragment name=\"Siegpunkte\"><aggregation>SUM</aggregation><relevantForRanking>true</relevantForRanking></fragment></definition><scores><entry><player team=\"ONE\"/><score><part>2</part></score></entry><entry><player team=\"TWO\"/><score><part>0</part></score></entry></scores><winner team=\"ONE\" regular=\"true\" reason=\"gewonnen\"/></data></room>",
+            room_id
+        )
+    }
+
+    #[test]
+    fn test_tracks_interleaved_rooms() {
+        let inbound = [
+            "<protocol>".to_string(),
+            "<observed roomId=\"a\"/><observed roomId=\"b\"/>".to_string(),
+            memento_xml("a", 0),
+            memento_xml("b", 0),
+            memento_xml("a", 1),
+            result_xml("a"),
+            memento_xml("b", 1),
+            "</protocol>".to_string(),
+        ];
+        let transcript = Transcript {
+            entries: inbound
+                .into_iter()
+                .map(|data| TranscriptEntry {
+                    elapsed: Duration::ZERO,
+                    direction: TrafficDirection::Inbound,
+                    data,
+                })
+                .collect(),
+        };
+        let msgs = replay_messages::<Piranhas>(transcript, &Piranhas::game_type()).unwrap();
+
+        let mut tracker: RoomTracker = RoomTracker::new();
+        let events: Vec<SpectatorEvent> = msgs
+            .into_iter()
+            .filter_map(|msg| tracker.handle(msg))
+            .collect();
+
+        let kinds: Vec<(&str, &str)> = events
+            .iter()
+            .map(|event| {
+                let kind = match event {
+                    SpectatorEvent::GameStarted { .. } => "started",
+                    SpectatorEvent::MovePlayed { .. } => "move",
+                    SpectatorEvent::GameFinished { .. } => "finished",
+                };
+                (event.room_id(), kind)
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("a", "started"),
+                ("b", "started"),
+                ("a", "move"),
+                ("a", "finished"),
+                ("b", "move"),
+            ]
+        );
+
+        let room_a = tracker.room("a").unwrap();
+        assert_eq!(room_a.states.len(), 2);
+        assert_eq!(room_a.latest().unwrap().turn, 1);
+        assert!(room_a.is_finished());
+
+        let room_b = tracker.room("b").unwrap();
+        assert_eq!(room_b.states.len(), 2);
+        assert!(!room_b.is_finished());
+    }
+}
@@ -0,0 +1,88 @@
+mod common;
+
+#[cfg(test)]
+pub mod tests {
+    use socha::{
+        i_client_handler::state_tracker::StateTracker,
+        internal::FieldDiff,
+        neutral::{PiranhaField, Team},
+    };
+
+    use crate::common::start_state;
+
+    #[test]
+    fn test_tracks_own_and_opponent_moves() {
+        let start = start_state();
+        let mut tracker = StateTracker::new();
+        tracker.set_team(start.current_team());
+        assert!(tracker.update(&start).is_none());
+
+        // our move, the server sends it back as last move
+        let mut after_ours = start.clone();
+        let ours = start.possible_moves()[0];
+        after_ours.perform_move(ours);
+        tracker.sent_move(ours);
+        let transition = tracker.update(&after_ours).unwrap();
+        assert_eq!(transition.mv, ours);
+        assert!(!transition.by_opponent);
+        assert!(transition.desync.is_none());
+
+        // the opponent's move is taken from last_move
+        let mut after_theirs = after_ours.clone();
+        let theirs = after_ours.possible_moves()[0];
+        after_theirs.perform_move(theirs);
+        let transition = tracker.update(&after_theirs).unwrap();
+        assert_eq!(transition.mv, theirs);
+        assert_eq!(transition.team, start.current_team().opponent());
+        assert!(transition.by_opponent);
+        assert!(!transition.inferred);
+        assert_eq!(transition.before, after_ours);
+        assert!(transition.desync.is_none());
+        assert_eq!(tracker.state(), Some(&after_theirs));
+
+        // without last_move the move is inferred from the board
+        let mut after_ours_again = after_theirs.clone();
+        let ours = after_theirs.possible_moves()[0];
+        after_ours_again.perform_move(ours);
+        after_ours_again.last_move = None;
+        let transition = tracker.update(&after_ours_again).unwrap();
+        assert_eq!(transition.mv, ours);
+        assert!(transition.inferred);
+        assert!(!transition.by_opponent);
+    }
+
+    #[test]
+    fn test_reports_desync_with_diff() {
+        let start = start_state();
+        let mut tracker = StateTracker::new();
+        tracker.set_team(Team::Two);
+        tracker.update(&start);
+
+        let mv = start.possible_moves()[0];
+        let mut received = start.clone();
+        received.perform_move(mv);
+        // the server's board has an extra squid, which our rules know nothing about
+        *received.board.get_mut(5, 5) = PiranhaField::Squid;
+
+        let desync = tracker
+            .update(&received)
+            .and_then(|transition| transition.desync)
+            .expect("desync not detected");
+        assert_eq!(desync.mv, mv);
+        assert_eq!(desync.before, start);
+        assert_eq!(
+            desync.diff,
+            vec![FieldDiff {
+                pos: (5, 5),
+                before: PiranhaField::Empty,
+                after: PiranhaField::Squid,
+            }]
+        );
+        assert!(desync.to_string().contains("(5, 5): EMPTY -> SQUID"));
+
+        // skipped mementos can't be checked
+        let mut later = received.clone();
+        later.turn += 3;
+        assert!(tracker.update(&later).is_none());
+    }
+}
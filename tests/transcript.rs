@@ -0,0 +1,88 @@
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        str::FromStr,
+        thread,
+        time::Duration,
+    };
+
+    use socha::{
+        error::ReceiveErr,
+        internal::ComMessage,
+        season::{piranhas::Piranhas, Season},
+        socha_com::ComHandler,
+        transcript::{
+            replay_messages, TrafficDirection, Transcript, TranscriptEntry, TranscriptRecorder,
+        },
+    };
+
+    #[test]
+    fn test_entry_escaping_round_trip() {
+        let entry = TranscriptEntry {
+            elapsed: Duration::from_millis(1250),
+            direction: TrafficDirection::Inbound,
+            data: "<room roomId=\"a\">\n  <data class=\"moveRequest\"/>\\\r\n</room>".to_string(),
+        };
+        let line = entry.to_string();
+        assert!(!line.contains('\n'));
+        assert!(line.starts_with("1.250 IN "));
+        assert_eq!(TranscriptEntry::from_str(&line).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut tmp = [0_u8; 1024];
+            let _ = stream.read(&mut tmp).unwrap();
+            // the ä of the room id is split between two reads
+            stream
+                .write_all(b"<protocol>\n  <joined roomId=\"r\xc3")
+                .unwrap();
+            thread::sleep(Duration::from_millis(20));
+            stream.write_all(b"\xa4um-1\"/>\n").unwrap();
+            thread::sleep(Duration::from_millis(20));
+            stream
+                .write_all(
+                    "<room roomId=\"räum-1\">\n<data class=\"welcomeMessage\" team=\"ONE\"/>\n</room>\n</protocol>".as_bytes(),
+                )
+                .unwrap();
+        });
+
+        let path =
+            std::env::temp_dir().join(format!("socha_transcript_test_{}.log", std::process::id()));
+        let recorder = TranscriptRecorder::create(&path).unwrap();
+        let mut com: ComHandler =
+            ComHandler::join_recorded(&addr, None, &Piranhas::game_type(), recorder).unwrap();
+        let mut live_msgs = Vec::new();
+        loop {
+            match com.wait_for_com_message(Duration::from_secs(5)) {
+                Ok(msg) => live_msgs.push(msg),
+                Err(ReceiveErr::ConnectionClosed(_)) => break,
+                Err(e) => panic!("unexpected receive error: {:?}", e),
+            }
+        }
+        drop(com);
+        server.join().unwrap();
+        assert_eq!(live_msgs.len(), 2);
+
+        let transcript = Transcript::read_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let first_out = transcript.outbound().next().unwrap();
+        assert_eq!(
+            first_out.data,
+            "<protocol><join gameType=\"swc_2026_piranhas\"/>"
+        );
+        let inbound: String = transcript.inbound().map(|e| e.data.as_str()).collect();
+        assert!(inbound.starts_with("<protocol>\n  <joined roomId=\"räum-1\"/>"));
+        assert!(inbound.ends_with("</protocol>"));
+
+        let replayed: Vec<ComMessage> =
+            replay_messages::<Piranhas>(transcript, &Piranhas::game_type()).unwrap();
+        assert_eq!(replayed, live_msgs);
+    }
+}